@@ -0,0 +1,234 @@
+use crate::parser::{AstNode, Expr, Op, Stmts, Type, UnaryOp};
+use std::fmt::{self, Write};
+
+const INDENT: &str = "    ";
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Nat => write!(f, "nat"),
+            Type::String => write!(f, "string"),
+            Type::Int => write!(f, "int"),
+            Type::Bool => write!(f, "bool"),
+            Type::Float => write!(f, "float"),
+            Type::Char => write!(f, "char"),
+            Type::Void => write!(f, "void"),
+            Type::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Add => write!(f, "+"),
+            Op::Minus => write!(f, "-"),
+            Op::Mult => write!(f, "*"),
+            Op::Div => write!(f, "/"),
+            Op::Mod => write!(f, "%"),
+            Op::Pow => write!(f, "**"),
+            Op::Equal => write!(f, "=="),
+            Op::NotEqual => write!(f, "!="),
+            Op::And => write!(f, "and"),
+            Op::Or => write!(f, "or"),
+            Op::LessThan => write!(f, "<"),
+            Op::LessThanEq => write!(f, "<="),
+            Op::GreaterThan => write!(f, ">"),
+            Op::GreaterThanEq => write!(f, ">="),
+        }
+    }
+}
+
+fn join_args(args: &[AstNode<Expr>]) -> String {
+    args.iter()
+        .map(|arg| arg.value.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn label_prefix(label: &Option<String>) -> String {
+    label
+        .as_ref()
+        .map(|label| format!("{label}: "))
+        .unwrap_or_default()
+}
+
+fn jump(keyword: &str, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("{keyword} {label}"),
+        None => keyword.to_string(),
+    }
+}
+
+//Binary expressions are always parenthesized so the shape of the tree is visible
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(num, suffix, _) => {
+                write!(f, "{num}{}", suffix.map_or("", |suffix| suffix.as_str()))
+            }
+            Expr::String(string) => write!(f, "{string:?}"),
+            Expr::Char(c) => write!(f, "{c:?}"),
+            Expr::Variable(name) => write!(f, "{name}"),
+            Expr::Bool(bool_val) => write!(f, "{bool_val}"),
+            Expr::Float(num) => write!(f, "{num:?}"),
+            Expr::SubprogramCall { name, args, .. } => write!(f, "{name}({})", join_args(args)),
+            Expr::Binary { op, lhs, rhs, .. } => write!(f, "({} {op} {})", lhs.value, rhs.value),
+            Expr::Unary { op, expr } => match op {
+                UnaryOp::Not => write!(f, "not {}", expr.value),
+                UnaryOp::Neg => write!(f, "-{}", expr.value),
+            },
+            Expr::Conditional {
+                cond,
+                then_expr,
+                else_expr,
+            } => write!(
+                f,
+                "({} ? {} : {})",
+                cond.value, then_expr.value, else_expr.value
+            ),
+            Expr::Index { expr, index } => write!(f, "{}[{}]", expr.value, index.value),
+            Expr::Bind { name, expr, .. } => write!(f, "({name} := {})", expr.value),
+        }
+    }
+}
+
+pub struct AstPrinter {
+    sink: String,
+    indent: usize,
+}
+
+impl AstPrinter {
+    pub fn new() -> Self {
+        Self {
+            sink: String::new(),
+            indent: 0,
+        }
+    }
+
+    fn line(self: &mut Self, text: &str) -> fmt::Result {
+        writeln!(self.sink, "{}{text}", INDENT.repeat(self.indent))
+    }
+
+    fn block(self: &mut Self, header: &str, stmts: &[AstNode<Stmts>]) -> fmt::Result {
+        self.line(header)?;
+        self.indent += 1;
+        self.print_stmts(stmts)?;
+        self.indent -= 1;
+        self.line("end")
+    }
+
+    fn print_stmts(self: &mut Self, stmts: &[AstNode<Stmts>]) -> fmt::Result {
+        for stmt in stmts {
+            match &stmt.value {
+                Stmts::Write { expr, newline, .. } => {
+                    let keyword = if *newline { "writeln" } else { "write" };
+                    self.line(&format!("{keyword}({})", expr.value))?;
+                }
+                Stmts::Return { expr, .. } => self.line(&format!("return {}", expr.value))?,
+                Stmts::Set {
+                    name,
+                    var_type,
+                    mutable,
+                    expr,
+                } => {
+                    let mutable = if *mutable { "mut " } else { "" };
+                    let line = match var_type {
+                        Type::Unknown => format!("set {mutable}{name} := {}", expr.value),
+                        _ => format!("set {mutable}{name}: {var_type} = {}", expr.value),
+                    };
+                    self.line(&line)?;
+                }
+                Stmts::Assign { name, expr } => self.line(&format!("{name} = {}", expr.value))?,
+                Stmts::SubProgramDef {
+                    name,
+                    return_type,
+                    params,
+                    stmts,
+                    doc,
+                    noreturn,
+                } => {
+                    for line in doc.iter().flat_map(|doc| doc.lines()) {
+                        self.line(&format!("/// {line}"))?;
+                    }
+                    let params = params
+                        .iter()
+                        .map(|param| {
+                            let mutable = if param.mutable { "mut " } else { "" };
+                            format!("{mutable}{}: {}", param.name, param.param_type)
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let noreturn = if *noreturn { " noreturn" } else { "" };
+                    self.block(
+                        &format!("subprogram {name}({params}): {return_type}{noreturn}"),
+                        stmts,
+                    )?;
+                }
+                Stmts::If { expr, stmts, .. } => {
+                    self.block(&format!("if {}", expr.value), stmts)?
+                }
+                Stmts::Else(stmts) => self.block("else", stmts)?,
+                Stmts::SubProgramCall { name, args } => {
+                    self.line(&format!("{name}({})", join_args(args)))?
+                }
+                Stmts::While {
+                    expr,
+                    stmts,
+                    else_stmts,
+                    label,
+                    ..
+                } => {
+                    let header = format!("{}while {}", label_prefix(label), expr.value);
+                    self.block(&header, stmts)?;
+                    if let Some(else_stmts) = else_stmts {
+                        self.block("else", else_stmts)?;
+                    }
+                }
+                Stmts::Break(label) => self.line(&jump("break", label))?,
+                Stmts::Continue(label) => self.line(&jump("continue", label))?,
+                Stmts::Until { expr, stmts, label } => {
+                    let header = format!("{}until {}", label_prefix(label), expr.value);
+                    self.block(&header, stmts)?
+                }
+                Stmts::Repeat {
+                    count,
+                    stmts,
+                    label,
+                } => {
+                    let header = format!("{}repeat {} times", label_prefix(label), count.value);
+                    self.block(&header, stmts)?
+                }
+                Stmts::For {
+                    var,
+                    start,
+                    end,
+                    stmts,
+                    label,
+                    ..
+                } => {
+                    let header = format!(
+                        "{}for {var} := {} to {}",
+                        label_prefix(label),
+                        start.value,
+                        end.value
+                    );
+                    self.block(&header, stmts)?
+                }
+                Stmts::DoWhile { stmts, expr, label } => {
+                    self.line(&format!("{}do", label_prefix(label)))?;
+                    self.indent += 1;
+                    self.print_stmts(stmts)?;
+                    self.indent -= 1;
+                    self.line(&format!("end while {}", expr.value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn print_ast(mut self, ast: &[AstNode<Stmts>]) -> Result<String, fmt::Error> {
+        self.print_stmts(ast)?;
+        Ok(self.sink)
+    }
+}
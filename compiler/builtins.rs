@@ -0,0 +1,90 @@
+use crate::parser::Type;
+
+//Subprograms that are provided by the compiler and the runtime instead of being defined in pseudo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    ReadLine,
+    ReadInt,
+    ReadNat,
+    Exit,
+    WriteHex,
+    WriteBin,
+    Min,
+    Max,
+    Abs,
+    ToString,
+    Args,
+    Arg,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Builtin> {
+        match name {
+            "read_line" => Some(Builtin::ReadLine),
+            "read_int" => Some(Builtin::ReadInt),
+            "read_nat" => Some(Builtin::ReadNat),
+            "exit" => Some(Builtin::Exit),
+            "write_hex" => Some(Builtin::WriteHex),
+            "write_bin" => Some(Builtin::WriteBin),
+            "min" => Some(Builtin::Min),
+            "max" => Some(Builtin::Max),
+            "abs" => Some(Builtin::Abs),
+            "to_string" => Some(Builtin::ToString),
+            "args" => Some(Builtin::Args),
+            "arg" => Some(Builtin::Arg),
+            _ => None,
+        }
+    }
+
+    pub fn param_types(&self) -> Vec<Type> {
+        match self {
+            Builtin::ReadLine | Builtin::ReadInt | Builtin::ReadNat | Builtin::Args => Vec::new(),
+            Builtin::Arg => vec![Type::Nat],
+            Builtin::Exit => vec![Type::Int],
+            //Either int or nat, see takes_integer
+            Builtin::WriteHex | Builtin::WriteBin => vec![Type::Unknown],
+            //Any numeric type, see is_numeric
+            Builtin::Min | Builtin::Max => vec![Type::Unknown, Type::Unknown],
+            //Anything that can be printed, checked by the analyzer
+            Builtin::Abs | Builtin::ToString => vec![Type::Unknown],
+        }
+    }
+
+    //Generic over the numeric types, they return the type of their arguments
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Builtin::Min | Builtin::Max | Builtin::Abs)
+    }
+
+    pub fn accepts_numeric(&self, type_: Type) -> bool {
+        match self {
+            Builtin::Abs => matches!(type_, Type::Int | Type::Float),
+            _ => matches!(type_, Type::Int | Type::Nat | Type::Float),
+        }
+    }
+
+    //The argument may be any integer type so it can't be described by param_types alone
+    pub fn takes_integer(&self) -> bool {
+        matches!(self, Builtin::WriteHex | Builtin::WriteBin)
+    }
+
+    //Lowered to C that is not an expression so they can't be used as a value
+    pub fn is_statement_only(&self) -> bool {
+        matches!(self, Builtin::WriteHex | Builtin::WriteBin)
+    }
+
+    //argc and argv only exist in the C main
+    pub fn is_main_only(&self) -> bool {
+        matches!(self, Builtin::Args | Builtin::Arg)
+    }
+
+    pub fn return_type(&self) -> Type {
+        match self {
+            Builtin::ReadLine | Builtin::ToString | Builtin::Arg => Type::String,
+            Builtin::ReadInt => Type::Int,
+            Builtin::ReadNat | Builtin::Args => Type::Nat,
+            Builtin::Exit | Builtin::WriteHex | Builtin::WriteBin => Type::Void,
+            //Decided by the arguments
+            Builtin::Min | Builtin::Max | Builtin::Abs => Type::Unknown,
+        }
+    }
+}
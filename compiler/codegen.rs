@@ -1,49 +1,250 @@
+use crate::builtins::Builtin;
+use crate::config::Config;
 use crate::ir::{CParam, CType, CValue, Cir};
+use crate::parser::{AstNode, Position};
 use std::fmt::{self, Write};
 
+//Helpers the generated code can call into. They are static inline so that unused ones
+//don't make cc complain
+const PRELUDE_HELPERS: &str = r#"static inline int32_t pseudo_read_int(void) {
+    int32_t value;
+    if (scanf("%" SCNd32, &value) != 1) {
+        fprintf(stderr, "error: could not read an int from stdin\n");
+        exit(1);
+    }
+    return value;
+}
+static inline uint32_t pseudo_read_nat(void) {
+    uint32_t value;
+    if (scanf("%" SCNu32, &value) != 1) {
+        fprintf(stderr, "error: could not read a nat from stdin\n");
+        exit(1);
+    }
+    return value;
+}
+static inline void pseudo_print_int(int32_t value) {
+    printf("%" PRId32, value);
+}
+static inline void pseudo_print_nat(uint32_t value) {
+    printf("%" PRIu32, value);
+}
+static inline void pseudo_print_char(char value) {
+    printf("%c", value);
+}
+static inline void pseudo_print_bin(uint32_t value) {
+    char digits[32];
+    int len = 0;
+    do {
+        digits[len++] = '0' + (value & 1);
+        value >>= 1;
+    } while (value > 0);
+    while (len > 0) putchar(digits[--len]);
+}
+static inline void pseudo_print_float(double value) {
+    printf("%f", value);
+}
+static inline void pseudo_overflow(const char* location) {
+    fprintf(stderr, "%s: error: integer overflow, aborting\n", location);
+    exit(1);
+}
+static inline char pseudo_string_at(string_t string, uint32_t index, const char* location) {
+    if (index >= string.len) {
+        fprintf(stderr, "%s: error: string index %" PRIu32 " is out of bounds for length %zu, aborting\n",
+                location, index, string.len);
+        exit(1);
+    }
+    return string.data[index];
+}
+#define PSEUDO_CHECKED(name, type, builtin)                               \
+    static inline type name(type lhs, type rhs, const char* location) {   \
+        type result;                                                      \
+        if (builtin(lhs, rhs, &result)) pseudo_overflow(location);        \
+        return result;                                                    \
+    }
+PSEUDO_CHECKED(pseudo_checked_add_i32, int32_t, __builtin_add_overflow)
+PSEUDO_CHECKED(pseudo_checked_sub_i32, int32_t, __builtin_sub_overflow)
+PSEUDO_CHECKED(pseudo_checked_mul_i32, int32_t, __builtin_mul_overflow)
+PSEUDO_CHECKED(pseudo_checked_add_u32, uint32_t, __builtin_add_overflow)
+PSEUDO_CHECKED(pseudo_checked_sub_u32, uint32_t, __builtin_sub_overflow)
+PSEUDO_CHECKED(pseudo_checked_mul_u32, uint32_t, __builtin_mul_overflow)
+//Integer pow multiplies in unsigned arithmetic so a large exponent wraps around instead of
+//being undefined behaviour, it is not caught by --checked. A negative exponent gives 0 unless
+//the base is 1 or -1, just like truncating the real result
+static inline uint32_t pseudo_pow_u32(uint32_t base, uint32_t exponent) {
+    uint32_t result = 1;
+    while (exponent > 0) {
+        if (exponent & 1) result *= base;
+        base *= base;
+        exponent >>= 1;
+    }
+    return result;
+}
+static inline int32_t pseudo_pow_i32(int32_t base, int32_t exponent) {
+    if (exponent < 0) {
+        if (base == 1) return 1;
+        if (base == -1) return (exponent & 1) ? -1 : 1;
+        return 0;
+    }
+    return (int32_t)pseudo_pow_u32((uint32_t)base, (uint32_t)exponent);
+}
+//min, max and abs are picked by the C type of their first argument. The helpers evaluate every
+//argument once, and abs of the smallest int wraps around to itself
+#define PSEUDO_MIN_MAX(type, suffix)                                                  \
+    static inline type pseudo_min_##suffix(type a, type b) { return a < b ? a : b; }  \
+    static inline type pseudo_max_##suffix(type a, type b) { return a > b ? a : b; }
+PSEUDO_MIN_MAX(int32_t, i32)
+PSEUDO_MIN_MAX(uint32_t, u32)
+PSEUDO_MIN_MAX(double, f64)
+static inline int32_t pseudo_abs_i32(int32_t value) {
+    return (int32_t)(value < 0 ? 0u - (uint32_t)value : (uint32_t)value);
+}
+#define PSEUDO_NUMERIC(name, value)                                                   \
+    _Generic((value), int32_t: name##_i32, uint32_t: name##_u32, double: name##_f64)
+#define pseudo_min(a, b) PSEUDO_NUMERIC(pseudo_min, a)(a, b)
+#define pseudo_max(a, b) PSEUDO_NUMERIC(pseudo_max, a)(a, b)
+#define pseudo_abs(value) _Generic((value), int32_t: pseudo_abs_i32, double: fabs)(value)
+#define PSEUDO_TO_STR(name, type, format)                        \
+    static inline string_t name(tgc_t* gc, type value) {         \
+        int len = snprintf(NULL, 0, format, value);               \
+        char* data = tgc_alloc(gc, len + 1);                      \
+        snprintf(data, len + 1, format, value);                   \
+        return (string_t){ data, (size_t)len };                   \
+    }
+PSEUDO_TO_STR(pseudo_int_to_str, int32_t, "%" PRId32)
+PSEUDO_TO_STR(pseudo_nat_to_str, uint32_t, "%" PRIu32)
+PSEUDO_TO_STR(pseudo_float_to_str, double, "%f")
+PSEUDO_TO_STR(pseudo_char_to_str, char, "%c")
+static inline string_t pseudo_bool_to_str(bool value) {
+    return value ? StrLit("true") : StrLit("false");
+}
+static inline _Noreturn void pseudo_exit(int32_t code) {
+    tgc_stop(&gc);
+    exit(code);
+}
+//arg(i) is bounds checked, asking for an argument past args() stops the program
+static inline string_t pseudo_arg(int argc, char** argv, uint32_t i) {
+    if (i >= (uint32_t)argc) {
+        fprintf(stderr, "error: arg(%" PRIu32 ") is out of bounds, args() is %d\n", i, argc);
+        pseudo_exit(1);
+    }
+    return (string_t){ argv[i], strlen(argv[i]) };
+}
+//a + b + c is joined with one allocation instead of one per +
+static inline string_t string_concat_n(tgc_t* gc, size_t n, ...) {
+    va_list pieces;
+    size_t len = 0;
+    va_start(pieces, n);
+    for (size_t i = 0; i < n; i++) len += va_arg(pieces, string_t).len;
+    va_end(pieces);
+    char* data = tgc_alloc(gc, len + 1);
+    size_t offset = 0;
+    va_start(pieces, n);
+    for (size_t i = 0; i < n; i++) {
+        string_t piece = va_arg(pieces, string_t);
+        memcpy(data + offset, piece.data, piece.len);
+        offset += piece.len;
+    }
+    va_end(pieces);
+    data[len] = '\0';
+    return (string_t){ data, len };
+}"#;
+
+//What a break or continue needs to know about a loop that is being generated
+struct LoopCtx {
+    label: Option<String>,
+    //Set by a break so the else of a while can tell how the loop ended
+    did_break: Option<String>,
+    //Labeled loops get __break_<suffix> after them and __continue_<suffix> at the end of their
+    //body, for the goto of a break or continue that comes from a nested loop
+    goto_suffix: Option<String>,
+}
+
 pub struct CodeGen {
     sink: String,
+    //There is a single collector for the whole program. It is started when main starts and
+    //stopped on every way out of main, other subprograms never start or stop it
     is_main: bool,
+    repeat_count: usize,
+    for_count: usize,
+    did_break_count: usize,
+    label_count: usize,
+    prelude: bool,
+    //One entry per loop being generated, innermost last
+    loops: Vec<LoopCtx>,
 }
 
 impl CodeGen {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
         Self {
             sink: String::new(),
             is_main: false,
+            repeat_count: 0,
+            for_count: 0,
+            did_break_count: 0,
+            label_count: 0,
+            prelude: config.prelude,
+            loops: Vec::new(),
         }
     }
 
     fn generate_prelude(self: &mut Self) -> fmt::Result {
         writeln!(self.sink, "#include <pseudo.h>")?;
+        writeln!(self.sink, "#include <inttypes.h>")?;
+        writeln!(self.sink, "#include <math.h>")?;
+        writeln!(self.sink, "#include <stdarg.h>")?;
+        writeln!(self.sink, "#include <stdio.h>")?;
+        writeln!(self.sink, "#include <stdlib.h>")?;
+        writeln!(self.sink, "#include <string.h>")?;
         writeln!(self.sink, "static tgc_t gc;")?;
+        writeln!(self.sink, "{PRELUDE_HELPERS}")?;
         Ok(())
     }
 
-    fn generate_write_stmt(self: &mut Self, ctype: &CType, cvalue: &CValue) -> fmt::Result {
+    //Points cc diagnostics for the following C lines back at the pseudo source
+    fn generate_line_directive(self: &mut Self, position: &Position) -> fmt::Result {
+        let filename = position.filename.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(self.sink, "#line {} \"{filename}\"", position.row)?;
+        Ok(())
+    }
+
+    fn generate_write_stmt(
+        self: &mut Self,
+        ctype: &CType,
+        cvalue: &CValue,
+        newline: bool,
+    ) -> fmt::Result {
         let print_func = match ctype {
             CType::Int => {
-                format!("print_int({cvalue})")
+                format!("pseudo_print_int({cvalue})")
             }
             CType::Uint => {
-                format!("print_uint({cvalue})")
+                format!("pseudo_print_nat({cvalue})")
             }
             CType::String => {
                 format!("print_str({cvalue})")
             }
+            CType::Float => {
+                format!("pseudo_print_float({cvalue})")
+            }
             CType::Bool => {
                 format!("print_bool({cvalue})")
             }
+            CType::Char => {
+                format!("pseudo_print_char({cvalue})")
+            }
             CType::Void => {
-                //TODO: Warn that you cannot print void values
-                format!("void")
+                unreachable!("writing a void value should have been rejected by semantic analysis")
             }
         };
         writeln!(self.sink, "{print_func};")?;
+        if newline {
+            writeln!(self.sink, "print_str(StrLit(\"\\n\"));")?;
+        }
         Ok(())
     }
 
     fn generate_return_stmt(self: &mut Self, cvalue: &CValue) -> fmt::Result {
+        //is_main holds for the whole body, so a return nested in an if or a loop stops it too
         if self.is_main {
             writeln!(self.sink, "tgc_stop(&gc);")?;
         }
@@ -51,17 +252,18 @@ impl CodeGen {
         Ok(())
     }
 
-    fn generate_subprogdef_stmt(
+    fn generate_subprog_signature(
         self: &mut Self,
-        name: String,
-        cparams: Vec<CParam>,
+        name: &str,
+        cparams: &[CParam],
         return_type: &CType,
-        stmts: Vec<Cir>,
+        noreturn: bool,
     ) -> fmt::Result {
-        self.is_main = &name == "main";
+        if noreturn {
+            write!(self.sink, "_Noreturn ")?;
+        }
         write!(self.sink, "{return_type} {name}")?;
-
-        if self.is_main {
+        if name == "main" {
             write!(self.sink, "(int argc, char** argv)")?;
         } else {
             let param_strings: Vec<String> = cparams
@@ -70,35 +272,260 @@ impl CodeGen {
                 .collect();
             write!(self.sink, "({})", param_strings.join(", "))?;
         }
+        Ok(())
+    }
+
+    //Pseudo subprograms can be called before they are defined but C functions can't
+    fn generate_prototypes(self: &mut Self, ir: &[AstNode<Cir>]) -> fmt::Result {
+        for node in ir {
+            if let Cir::SubProgDef {
+                name,
+                cparams,
+                return_type,
+                noreturn,
+                ..
+            } = &node.value
+            {
+                self.generate_subprog_signature(name, cparams, return_type, *noreturn)?;
+                writeln!(self.sink, ";")?;
+            }
+        }
+        Ok(())
+    }
 
+    fn generate_subprogdef_stmt(
+        self: &mut Self,
+        name: &str,
+        cparams: &[CParam],
+        return_type: &CType,
+        stmts: &[AstNode<Cir>],
+        noreturn: bool,
+    ) -> fmt::Result {
+        self.is_main = name == "main";
+        self.generate_subprog_signature(name, cparams, return_type, noreturn)?;
         writeln!(self.sink, "{{")?;
         if self.is_main {
             writeln!(self.sink, "tgc_start(&gc, &argc);")?;
         }
+        let ends_in_return = matches!(
+            stmts.last(),
+            Some(AstNode {
+                value: Cir::Return(_),
+                ..
+            })
+        );
         self.generate_stmts(stmts)?;
+        if self.is_main && !ends_in_return {
+            self.generate_return_stmt(&CValue::NumLiteral(0, CType::Int))?;
+        }
         writeln!(self.sink, "}}")?;
         Ok(())
     }
 
-    fn generate_if_stmt(self: &mut Self, expr: CValue, stmts: Vec<Cir>) -> fmt::Result {
+    fn generate_if_stmt(self: &mut Self, expr: &CValue, stmts: &[AstNode<Cir>]) -> fmt::Result {
         writeln!(self.sink, "if ({expr}) {{")?;
         self.generate_stmts(stmts)?;
         writeln!(self.sink, "}}")?;
         Ok(())
     }
 
-    fn generate_while_stmt(self: &mut Self, expr: CValue, stmts: Vec<Cir>) -> fmt::Result {
-        writeln!(self.sink, "while ({expr}) {{")?;
+    //C labels are per function so the same pseudo label on two loops needs two names
+    fn goto_suffix(self: &mut Self, label: &Option<String>) -> Option<String> {
+        let suffix = format!("{}_{}", label.as_ref()?, self.label_count);
+        self.label_count += 1;
+        Some(suffix)
+    }
+
+    fn generate_loop_body(
+        self: &mut Self,
+        ctx: LoopCtx,
+        stmts: &[AstNode<Cir>],
+    ) -> Result<Option<String>, fmt::Error> {
+        self.loops.push(ctx);
         self.generate_stmts(stmts)?;
+        let ctx = self.loops.pop().expect("The loop was just pushed");
+        if let Some(suffix) = &ctx.goto_suffix {
+            writeln!(self.sink, "__continue_{suffix}: ;")?;
+        }
+        Ok(ctx.goto_suffix)
+    }
+
+    fn generate_repeat_stmt(
+        self: &mut Self,
+        count: &CValue,
+        stmts: &[AstNode<Cir>],
+        label: &Option<String>,
+    ) -> fmt::Result {
+        //User names can't start with __ so the counter never shadows one. The count is
+        //evaluated once, the body may change what it was computed from
+        let counter = format!("__repeat_{}", self.repeat_count);
+        let limit = format!("__repeat_count_{}", self.repeat_count);
+        self.repeat_count += 1;
+        writeln!(self.sink, "const uint32_t {limit} = ({count});")?;
+        writeln!(
+            self.sink,
+            "for (uint32_t {counter} = 0; {counter} < {limit}; {counter}++) {{"
+        )?;
+        let goto_suffix = self.goto_suffix(label);
+        let ctx = LoopCtx {
+            label: label.clone(),
+            did_break: None,
+            goto_suffix,
+        };
+        let goto_suffix = self.generate_loop_body(ctx, stmts)?;
+        writeln!(self.sink, "}}")?;
+        if let Some(suffix) = goto_suffix {
+            writeln!(self.sink, "__break_{suffix}: ;")?;
+        }
+        Ok(())
+    }
+
+    //The counter is only stepped while it is below the end, so an end at the largest value of
+    //the type does not overflow it. The body gets a const copy it can't change
+    fn generate_for_stmt(
+        self: &mut Self,
+        var: &str,
+        var_type: &CType,
+        start: &CValue,
+        end: &CValue,
+        stmts: &[AstNode<Cir>],
+        label: &Option<String>,
+    ) -> fmt::Result {
+        let counter = format!("__for_{}", self.for_count);
+        let last = format!("__for_end_{}", self.for_count);
+        let go = format!("__for_go_{}", self.for_count);
+        self.for_count += 1;
+        writeln!(self.sink, "const {var_type} {last} = ({end});")?;
+        writeln!(self.sink, "{var_type} {counter} = ({start});")?;
+        writeln!(
+            self.sink,
+            "for (bool {go} = {counter} <= {last}; {go}; {go} = {counter} != {last} && ({counter}++, true)) {{"
+        )?;
+        writeln!(self.sink, "const {var_type} {var} = {counter};")?;
+        let goto_suffix = self.goto_suffix(label);
+        let ctx = LoopCtx {
+            label: label.clone(),
+            did_break: None,
+            goto_suffix,
+        };
+        let goto_suffix = self.generate_loop_body(ctx, stmts)?;
         writeln!(self.sink, "}}")?;
+        if let Some(suffix) = goto_suffix {
+            writeln!(self.sink, "__break_{suffix}: ;")?;
+        }
+        Ok(())
+    }
+
+    //A continue jumps to the condition like in any C do while
+    fn generate_do_while_stmt(
+        self: &mut Self,
+        expr: &CValue,
+        stmts: &[AstNode<Cir>],
+        label: &Option<String>,
+    ) -> fmt::Result {
+        writeln!(self.sink, "do {{")?;
+        let goto_suffix = self.goto_suffix(label);
+        let ctx = LoopCtx {
+            label: label.clone(),
+            did_break: None,
+            goto_suffix,
+        };
+        let goto_suffix = self.generate_loop_body(ctx, stmts)?;
+        writeln!(self.sink, "}} while ({expr});")?;
+        if let Some(suffix) = goto_suffix {
+            writeln!(self.sink, "__break_{suffix}: ;")?;
+        }
+        Ok(())
+    }
+
+    fn generate_while_stmt(
+        self: &mut Self,
+        expr: &CValue,
+        stmts: &[AstNode<Cir>],
+        else_stmts: &Option<Vec<AstNode<Cir>>>,
+        label: &Option<String>,
+    ) -> fmt::Result {
+        //The else only runs when no break set the flag
+        let did_break = else_stmts.as_ref().map(|_| {
+            let flag = format!("__did_break_{}", self.did_break_count);
+            self.did_break_count += 1;
+            flag
+        });
+        if let Some(flag) = &did_break {
+            writeln!(self.sink, "bool {flag} = false;")?;
+        }
+        writeln!(self.sink, "while ({expr}) {{")?;
+        let goto_suffix = self.goto_suffix(label);
+        let ctx = LoopCtx {
+            label: label.clone(),
+            did_break: did_break.clone(),
+            goto_suffix,
+        };
+        let goto_suffix = self.generate_loop_body(ctx, stmts)?;
+        writeln!(self.sink, "}}")?;
+        if let (Some(flag), Some(else_stmts)) = (did_break, else_stmts) {
+            writeln!(self.sink, "if (!{flag}) {{")?;
+            self.generate_stmts(else_stmts)?;
+            writeln!(self.sink, "}}")?;
+        }
+        //A labeled break skips the else just like a plain one
+        if let Some(suffix) = goto_suffix {
+            writeln!(self.sink, "__break_{suffix}: ;")?;
+        }
+        Ok(())
+    }
+
+    //Index into loops of the loop a break or continue targets, semantic analysis made sure
+    //that it exists
+    fn target_loop(self: &Self, label: &Option<String>) -> usize {
+        match label {
+            Some(label) => self
+                .loops
+                .iter()
+                .rposition(|ctx| ctx.label.as_ref() == Some(label))
+                .expect("Unknown loop labels are rejected by semantic analysis"),
+            None => self.loops.len() - 1,
+        }
+    }
+
+    //Plain C break and continue when the target is the innermost loop, a goto otherwise
+    fn generate_break_stmt(self: &mut Self, label: &Option<String>) -> fmt::Result {
+        let target = self.target_loop(label);
+        let ctx = &self.loops[target];
+        if target + 1 == self.loops.len() {
+            if let Some(flag) = &ctx.did_break {
+                writeln!(self.sink, "{flag} = true;")?;
+            }
+            writeln!(self.sink, "break;")?;
+        } else {
+            let suffix = ctx
+                .goto_suffix
+                .as_ref()
+                .expect("Labeled loops have a suffix");
+            writeln!(self.sink, "goto __break_{suffix};")?;
+        }
+        Ok(())
+    }
+
+    fn generate_continue_stmt(self: &mut Self, label: &Option<String>) -> fmt::Result {
+        let target = self.target_loop(label);
+        if target + 1 == self.loops.len() {
+            writeln!(self.sink, "continue;")?;
+        } else {
+            let suffix = self.loops[target]
+                .goto_suffix
+                .as_ref()
+                .expect("Labeled loops have a suffix");
+            writeln!(self.sink, "goto __continue_{suffix};")?;
+        }
         Ok(())
     }
 
     fn generate_set_stmt(
         self: &mut Self,
-        name: String,
-        var_type: CType,
-        expr: CValue,
+        name: &str,
+        var_type: &CType,
+        expr: &CValue,
         mutable: bool,
     ) -> fmt::Result {
         if !mutable {
@@ -108,7 +535,7 @@ impl CodeGen {
         Ok(())
     }
 
-    fn generate_subprogcall_stmt(self: &mut Self, name: String, args: Vec<CValue>) -> fmt::Result {
+    fn generate_subprogcall_stmt(self: &mut Self, name: &str, args: &[CValue]) -> fmt::Result {
         let args_str = args
             .iter()
             .map(|arg| arg.to_string())
@@ -118,51 +545,131 @@ impl CodeGen {
         Ok(())
     }
 
-    fn generate_varassign_stmt(self: &mut Self, name: String, expr: CValue) -> fmt::Result {
+    fn generate_builtincall_stmt(
+        self: &mut Self,
+        builtin: Builtin,
+        args: &[CValue],
+    ) -> fmt::Result {
+        writeln!(
+            self.sink,
+            "{};",
+            CValue::BuiltinCall(builtin, args.to_vec())
+        )?;
+        Ok(())
+    }
+
+    fn generate_varassign_stmt(self: &mut Self, name: &str, expr: &CValue) -> fmt::Result {
         writeln!(self.sink, "{name} = {expr};")?;
         Ok(())
     }
 
-    fn generate_else_stmt(self: &mut Self, stmts: Vec<Cir>) -> fmt::Result {
+    fn generate_discard_stmt(self: &mut Self, expr: &CValue) -> fmt::Result {
+        writeln!(self.sink, "(void)({expr});")?;
+        Ok(())
+    }
+
+    fn generate_else_stmt(self: &mut Self, stmts: &[AstNode<Cir>]) -> fmt::Result {
+        //An else holding only an if and its own else is written as an else if chain
+        let else_if = match stmts {
+            [if_stmt] => Some((if_stmt, None)),
+            [if_stmt, else_stmt] if matches!(else_stmt.value, Cir::Else(_)) => {
+                Some((if_stmt, Some(else_stmt)))
+            }
+            _ => None,
+        };
+        if let Some((
+            AstNode {
+                value: Cir::If(cvalue, if_cir),
+                ..
+            },
+            else_stmt,
+        )) = else_if
+        {
+            write!(self.sink, "else ")?;
+            self.generate_if_stmt(cvalue, if_cir)?;
+            if let Some(AstNode {
+                value: Cir::Else(else_cir),
+                ..
+            }) = else_stmt
+            {
+                self.generate_else_stmt(else_cir)?;
+            }
+            return Ok(());
+        }
         writeln!(self.sink, "else {{")?;
         self.generate_stmts(stmts)?;
         writeln!(self.sink, "}}")?;
         Ok(())
     }
 
-    fn generate_stmts(self: &mut Self, stmts: Vec<Cir>) -> fmt::Result {
+    fn generate_stmts(self: &mut Self, stmts: &[AstNode<Cir>]) -> fmt::Result {
         for stmt in stmts {
-            match stmt {
-                Cir::Write(ctype, cvalue) => self.generate_write_stmt(&ctype, &cvalue)?,
-                Cir::Return(cvalue) => self.generate_return_stmt(&cvalue)?,
+            self.generate_line_directive(&stmt.position)?;
+            match &stmt.value {
+                Cir::Write(ctype, cvalue, newline) => {
+                    self.generate_write_stmt(ctype, cvalue, *newline)?
+                }
+                Cir::Return(cvalue) => self.generate_return_stmt(cvalue)?,
                 Cir::If(cvalue, stmts_cir) => self.generate_if_stmt(cvalue, stmts_cir)?,
-                Cir::While(cvalue, stmts_cir) => self.generate_while_stmt(cvalue, stmts_cir)?,
+                Cir::While(cvalue, stmts_cir, else_cir, label) => {
+                    self.generate_while_stmt(cvalue, stmts_cir, else_cir, label)?
+                }
+                Cir::Break(label) => self.generate_break_stmt(label)?,
+                Cir::Continue(label) => self.generate_continue_stmt(label)?,
+                Cir::Repeat(cvalue, stmts_cir, label) => {
+                    self.generate_repeat_stmt(cvalue, stmts_cir, label)?
+                }
+                Cir::For(var, var_type, start, end, stmts_cir, label) => {
+                    self.generate_for_stmt(var, var_type, start, end, stmts_cir, label)?
+                }
+                Cir::DoWhile(cvalue, stmts_cir, label) => {
+                    self.generate_do_while_stmt(cvalue, stmts_cir, label)?
+                }
                 Cir::Else(stmts_cir) => self.generate_else_stmt(stmts_cir)?,
                 Cir::SubProgDef {
                     name,
                     return_type,
                     stmts_cir,
                     cparams,
+                    noreturn,
                 } => {
                     self.generate_subprogdef_stmt(
-                        name.to_string(),
+                        name,
                         cparams,
-                        &return_type,
+                        return_type,
                         stmts_cir,
+                        *noreturn,
                     )?;
                 }
                 Cir::SubProgramCall(name, args) => self.generate_subprogcall_stmt(name, args)?,
+                Cir::BuiltinCall(builtin, args) => {
+                    self.generate_builtincall_stmt(*builtin, args)?
+                }
                 Cir::VariableDef(name, var_type, cvalue, mutable) => {
-                    self.generate_set_stmt(name, var_type, cvalue, mutable)?
+                    self.generate_set_stmt(name, var_type, cvalue, *mutable)?
                 }
                 Cir::VarAssign(name, cvalue) => self.generate_varassign_stmt(name, cvalue)?,
+                Cir::Discard(cvalue) => self.generate_discard_stmt(cvalue)?,
+                Cir::Scope(stmts_cir) => {
+                    writeln!(self.sink, "{{")?;
+                    self.generate_stmts(stmts_cir)?;
+                    writeln!(self.sink, "}}")?;
+                }
+                Cir::VariableDecl(name, var_type) => writeln!(self.sink, "{var_type} {name};")?,
             }
         }
         Ok(())
     }
 
-    pub fn generate_c_code(self: &mut Self, ir: Vec<Cir>) -> Result<String, std::fmt::Error> {
-        self.generate_prelude()?;
+    pub fn generate_c_code(
+        self: &mut Self,
+        ir: &[AstNode<Cir>],
+    ) -> Result<String, std::fmt::Error> {
+        if self.prelude {
+            self.generate_prelude()?;
+        }
+        //Both passes borrow the same IR, the prototypes first and then the bodies
+        self.generate_prototypes(ir)?;
         self.generate_stmts(ir)?;
         Ok(self.sink.clone())
     }
@@ -0,0 +1,47 @@
+use crate::lexer::DEFAULT_TAB_WIDTH;
+
+pub const DEFAULT_MAX_ERRORS: usize = 20;
+
+//Everything the command line can change about a compilation. main.rs builds it from the flags
+//and each stage reads the options it cares about
+#[derive(Debug, Clone)]
+pub struct Config {
+    //Number of columns a tab advances in diagnostics
+    pub tab_width: usize,
+    //int and nat arithmetic exits with an error when it overflows
+    pub checked: bool,
+    //Warn about subprograms that are never called
+    pub unused_warnings: bool,
+    //Warn about if, while and else bodies that are empty
+    pub empty_body_warnings: bool,
+    //Warnings are reported as errors instead
+    pub werror: bool,
+    //A line break can end a statement instead of a semicolon
+    pub relaxed_semicolons: bool,
+    //Errors past this many are only counted when reported, 0 reports all of them
+    pub max_errors: usize,
+    //Write the includes and helpers the generated code relies on. Without them the C has to be
+    //given its declarations by whoever compiles it
+    pub prelude: bool,
+    //The subprogram that becomes the C main, it gets argc and argv and runs the collector
+    pub entry: String,
+    //String indexing checks the index and exits with an error when it is out of bounds
+    pub safe: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            tab_width: DEFAULT_TAB_WIDTH,
+            checked: false,
+            unused_warnings: true,
+            empty_body_warnings: true,
+            werror: false,
+            relaxed_semicolons: false,
+            max_errors: DEFAULT_MAX_ERRORS,
+            prelude: true,
+            entry: "main".to_string(),
+            safe: false,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::parser::Position;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Error,
+    //Reported but does not stop the program from being compiled
+    Warning,
+}
+
+//An error found while compiling, reported instead of exiting so the compiler can be embedded
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub level: Level,
+    //Names the kind of warning for tools that match on it instead of on the message
+    pub code: Option<&'static str>,
+    pub msg: String,
+    pub position: Position,
+}
+
+impl Diagnostic {
+    pub fn error(msg: String, position: Position) -> Diagnostic {
+        Diagnostic {
+            level: Level::Error,
+            code: None,
+            msg,
+            position,
+        }
+    }
+
+    pub fn warning(msg: String, position: Position) -> Diagnostic {
+        Diagnostic {
+            level: Level::Warning,
+            code: None,
+            msg,
+            position,
+        }
+    }
+
+    pub fn with_code(self, code: &'static str) -> Diagnostic {
+        Diagnostic {
+            code: Some(code),
+            ..self
+        }
+    }
+
+    //Display always colors the level, this leaves the escapes out for files and pipes
+    pub fn render(&self, color: bool) -> String {
+        let level = match (self.level, color) {
+            (Level::Error, true) => "\x1b[31merror:\x1b[0m",
+            (Level::Warning, true) => "\x1b[33mwarning:\x1b[0m",
+            (Level::Error, false) => "error:",
+            (Level::Warning, false) => "warning:",
+        };
+        format!(
+            "{}:{}:{}: {level} {}",
+            self.position.filename, self.position.row, self.position.column, self.msg
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(true))
+    }
+}
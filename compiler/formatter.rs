@@ -0,0 +1,341 @@
+use crate::lexer::{is_keyword, raw_byte};
+use crate::parser::{AstNode, Expr, Param, Stmts, Type, UnaryOp};
+use std::fmt::{self, Write};
+
+const INDENT: &str = "    ";
+
+//Turns text back into a literal the lexer reads as the same text
+fn escape(text: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if let Some(byte) = raw_byte(c) {
+            escaped.push_str(&format!("\\x{byte:02x}"));
+            continue;
+        }
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\x{:02x}", c as u8)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+//Names that were written as raw identifiers get their backticks back
+fn ident(name: &str) -> String {
+    if is_keyword(name) {
+        format!("`{name}`")
+    } else {
+        name.to_string()
+    }
+}
+
+//The parser only has parentheses around bindings, so a tree it built always reads back the
+//same way when printed flat
+fn format_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(num, suffix, _) => {
+            format!("{num}{}", suffix.map_or("", |suffix| suffix.as_str()))
+        }
+        //A float always needs its dot or it would come back as an integer
+        Expr::Float(num) if num.fract() == 0.0 && num.is_finite() => format!("{num}.0"),
+        Expr::Float(num) => format!("{num}"),
+        Expr::String(string) => format!("\"{}\"", escape(string, '"')),
+        Expr::Char(c) => format!("'{}'", escape(&c.to_string(), '\'')),
+        Expr::Variable(name) => ident(name),
+        Expr::Bool(bool_val) => bool_val.to_string(),
+        Expr::SubprogramCall { name, args, .. } => {
+            format!("{}({})", ident(name), format_args(args))
+        }
+        Expr::Binary { op, lhs, rhs, .. } => {
+            format!(
+                "{} {op} {}",
+                format_expr(&lhs.value),
+                format_expr(&rhs.value)
+            )
+        }
+        Expr::Unary { op, expr } => match op {
+            UnaryOp::Not => format!("not {}", format_expr(&expr.value)),
+            UnaryOp::Neg => format!("-{}", format_expr(&expr.value)),
+        },
+        Expr::Conditional {
+            cond,
+            then_expr,
+            else_expr,
+        } => format!(
+            "{} ? {} : {}",
+            format_expr(&cond.value),
+            format_expr(&then_expr.value),
+            format_expr(&else_expr.value)
+        ),
+        Expr::Index { expr, index } => {
+            format!(
+                "{}[{}]",
+                format_expr(&expr.value),
+                format_expr(&index.value)
+            )
+        }
+        Expr::Bind { name, expr, .. } => {
+            format!("({} := {})", ident(name), format_expr(&expr.value))
+        }
+    }
+}
+
+fn format_args(args: &[AstNode<Expr>]) -> String {
+    args.iter()
+        .map(|arg| format_expr(&arg.value))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn format_params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            let mutable = if param.mutable { "mut " } else { "" };
+            format!("{mutable}{}: {}", ident(&param.name), param.param_type)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn label_prefix(label: &Option<String>) -> String {
+    label
+        .as_ref()
+        .map(|label| format!("{}: ", ident(label)))
+        .unwrap_or_default()
+}
+
+fn jump(keyword: &str, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("{keyword} {};", ident(label)),
+        None => format!("{keyword};"),
+    }
+}
+
+//Prints the ast back as pseudo source with one statement per line, four space indents and
+//a blank line between top level subprograms. Plain comments are not part of the ast so only
+//doc comments survive
+pub struct Formatter {
+    sink: String,
+    indent: usize,
+}
+
+impl Formatter {
+    pub fn new() -> Self {
+        Self {
+            sink: String::new(),
+            indent: 0,
+        }
+    }
+
+    fn line(self: &mut Self, text: &str) -> fmt::Result {
+        writeln!(self.sink, "{}{text}", INDENT.repeat(self.indent))
+    }
+
+    fn body(self: &mut Self, stmts: &[AstNode<Stmts>]) -> fmt::Result {
+        self.indent += 1;
+        self.format_stmts(stmts)?;
+        self.indent -= 1;
+        Ok(())
+    }
+
+    //An else is its own statement right after the if it belongs to, and that if leaves the
+    //end to it
+    fn format_else(self: &mut Self, stmts: &[AstNode<Stmts>]) -> fmt::Result {
+        match stmts {
+            [if_stmt] | [if_stmt, _] if matches!(if_stmt.value, Stmts::If { .. }) => {
+                let Stmts::If {
+                    expr,
+                    stmts: if_stmts,
+                    ..
+                } = &if_stmt.value
+                else {
+                    unreachable!()
+                };
+                self.line(&format!("else if {} then", format_expr(&expr.value)))?;
+                self.body(if_stmts)?;
+                match stmts.get(1) {
+                    Some(AstNode {
+                        value: Stmts::Else(else_stmts),
+                        ..
+                    }) => self.format_else(else_stmts),
+                    _ => self.line("end"),
+                }
+            }
+            _ => {
+                self.line("else")?;
+                self.body(stmts)?;
+                self.line("end")
+            }
+        }
+    }
+
+    fn format_stmts(self: &mut Self, stmts: &[AstNode<Stmts>]) -> fmt::Result {
+        let mut stmts = stmts.iter().peekable();
+        while let Some(stmt) = stmts.next() {
+            match &stmt.value {
+                Stmts::Write { expr, newline, .. } => {
+                    let keyword = if *newline { "writeln" } else { "write" };
+                    //Braces in a written string literal would be read as interpolation
+                    let expr = match &expr.value {
+                        Expr::String(string) => {
+                            format_expr(&Expr::String(string.replace('{', "{{").replace('}', "}}")))
+                        }
+                        expr => format_expr(expr),
+                    };
+                    self.line(&format!("{keyword}({expr});"))?;
+                }
+                Stmts::Return { expr, .. } => {
+                    self.line(&format!("return {};", format_expr(&expr.value)))?
+                }
+                Stmts::Set {
+                    name,
+                    var_type,
+                    mutable,
+                    expr,
+                } => {
+                    let mutable = if *mutable { "mut " } else { "" };
+                    let name = ident(name);
+                    let expr = format_expr(&expr.value);
+                    let line = match var_type {
+                        Type::Unknown => format!("set {mutable}{name} := {expr};"),
+                        _ => format!("set {mutable}{name}: {var_type} = {expr};"),
+                    };
+                    self.line(&line)?;
+                }
+                Stmts::Assign { name, expr } => {
+                    self.line(&format!("{} = {};", ident(name), format_expr(&expr.value)))?
+                }
+                Stmts::SubProgramDef {
+                    name,
+                    return_type,
+                    params,
+                    stmts: body,
+                    doc,
+                    noreturn,
+                } => {
+                    for line in doc.iter().flat_map(|doc| doc.lines()) {
+                        match line {
+                            "" => self.line("///")?,
+                            _ => self.line(&format!("/// {line}"))?,
+                        }
+                    }
+                    let name = ident(name);
+                    let params = format_params(params);
+                    let noreturn = if *noreturn { " noreturn" } else { "" };
+                    match return_type {
+                        Type::Void => self.line(&format!("proc {name}({params}){noreturn}"))?,
+                        _ => {
+                            self.line(&format!("func {name}({params}): {return_type}{noreturn}"))?
+                        }
+                    }
+                    self.line("start")?;
+                    self.body(body)?;
+                    self.line("stop")?;
+                    if self.indent == 0 && stmts.peek().is_some() {
+                        self.line("")?;
+                    }
+                }
+                Stmts::If {
+                    expr, stmts: body, ..
+                } => {
+                    self.line(&format!("if {} then", format_expr(&expr.value)))?;
+                    self.body(body)?;
+                    if !matches!(
+                        stmts.peek(),
+                        Some(AstNode {
+                            value: Stmts::Else(_),
+                            ..
+                        })
+                    ) {
+                        self.line("end")?;
+                    }
+                }
+                Stmts::Else(body) => self.format_else(body)?,
+                Stmts::SubProgramCall { name, args } => {
+                    self.line(&format!("{}({});", ident(name), format_args(args)))?
+                }
+                Stmts::While {
+                    expr,
+                    stmts: body,
+                    else_stmts,
+                    label,
+                    ..
+                } => {
+                    let expr = format_expr(&expr.value);
+                    self.line(&format!("{}while {expr} do", label_prefix(label)))?;
+                    self.body(body)?;
+                    if let Some(else_stmts) = else_stmts {
+                        self.line("else")?;
+                        self.body(else_stmts)?;
+                    }
+                    self.line("end")?;
+                }
+                Stmts::Break(label) => self.line(&jump("break", label))?,
+                Stmts::Continue(label) => self.line(&jump("continue", label))?,
+                Stmts::Until {
+                    expr,
+                    stmts: body,
+                    label,
+                } => {
+                    let expr = format_expr(&expr.value);
+                    self.line(&format!("{}until {expr} do", label_prefix(label)))?;
+                    self.body(body)?;
+                    self.line("end")?;
+                }
+                Stmts::Repeat {
+                    count,
+                    stmts: body,
+                    label,
+                } => {
+                    let count = format_expr(&count.value);
+                    self.line(&format!("{}repeat {count} times", label_prefix(label)))?;
+                    self.body(body)?;
+                    self.line("end")?;
+                }
+                Stmts::For {
+                    var,
+                    start,
+                    end,
+                    stmts: body,
+                    label,
+                    ..
+                } => {
+                    self.line(&format!(
+                        "{}for {} := {} to {} do",
+                        label_prefix(label),
+                        ident(var),
+                        format_expr(&start.value),
+                        format_expr(&end.value)
+                    ))?;
+                    self.body(body)?;
+                    self.line("end")?;
+                }
+                Stmts::DoWhile {
+                    stmts: body,
+                    expr,
+                    label,
+                } => {
+                    self.line(&format!("{}do", label_prefix(label)))?;
+                    self.body(body)?;
+                    self.line(&format!("end while {};", format_expr(&expr.value)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn format_program(mut self, ast: &[AstNode<Stmts>]) -> Result<String, fmt::Error> {
+        self.format_stmts(ast)?;
+        Ok(self.sink)
+    }
+}
@@ -1,12 +1,21 @@
-use crate::parser::{AstNode, Expr, Op, Stmts, Type};
+use crate::builtins::Builtin;
+use crate::config::Config;
+use crate::lexer::raw_byte;
+use crate::parser::{AstNode, Expr, Op, Position, Stmts, Type, UnaryOp};
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CType {
     Int,
     Uint,
+    //string_t is a pointer and a length into memory owned by the collector, so it is passed
+    //and returned by value everywhere: params, returns, print_str and the string_concat_n
+    //helper. string_concat is the one runtime function that wants addresses and gets them
+    //from compound literals, it never writes through them
     String,
     Bool,
+    Float,
+    Char,
     Void,
 }
 
@@ -20,54 +29,194 @@ impl fmt::Display for CType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             CType::Int => write!(f, "int32_t"),
-            CType::Uint => write!(f, "unt32_t"),
+            CType::Uint => write!(f, "uint32_t"),
             CType::String => write!(f, "string_t"),
             CType::Bool => write!(f, "bool"),
+            CType::Float => write!(f, "double"),
+            CType::Char => write!(f, "char"),
             CType::Void => write!(f, "void"),
         }
     }
 }
+//Turns the string back into the body of a C string literal. C string literals can't span
+//lines and a \x escape in C eats every hex digit after it, so the literal is split in two
+//when a hex digit follows one
+fn escape_c_string(string: &str) -> String {
+    let mut escaped = String::new();
+    let mut after_hex_escape = false;
+    for c in string.chars() {
+        if after_hex_escape && c.is_ascii_hexdigit() {
+            escaped.push_str("\" \"");
+        }
+        after_hex_escape = false;
+        if let Some(byte) = raw_byte(c) {
+            escaped.push_str(&format!("\\x{byte:02x}"));
+            after_hex_escape = true;
+            continue;
+        }
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            c if c.is_ascii_control() => {
+                escaped.push_str(&format!("\\x{:02x}", c as u8));
+                after_hex_escape = true;
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+//Same as escape_c_string for the body of a C char literal
+fn escape_c_char(c: char) -> String {
+    if let Some(byte) = raw_byte(c) {
+        return format!("\\x{byte:02x}");
+    }
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\\' => "\\\\".to_string(),
+        '\'' => "\\'".to_string(),
+        c if c.is_ascii_control() => format!("\\x{:02x}", c as u8),
+        c => c.to_string(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum CValue {
-    NumLiteral(i128),
+    //Always int or nat, the type picks the C spelling
+    NumLiteral(i128, CType),
     StringLiteral(String),
+    CharLiteral(char),
     Bool(bool),
+    FloatLiteral(f64),
     Variable(String),
     BinaryOp(Box<CValue>, Op, Box<CValue>),
+    //Every piece of a chain of string +, in order
+    StringConcat(Vec<CValue>),
+    //Arithmetic that exits with the position of the operator when it overflows
+    CheckedOp(Box<CValue>, Op, Box<CValue>, CType, Position),
+    UnaryOp(UnaryOp, Box<CValue>),
+    //C has no ** so this calls into a prelude helper picked by the operand type
+    Pow(Box<CValue>, Box<CValue>, CType),
     SubProgCall(String, Vec<CValue>),
+    BuiltinCall(Builtin, Vec<CValue>),
+    //Converts a value of the given type into a string allocated by the GC
+    ToString(Box<CValue>, CType),
+    Conditional(Box<CValue>, Box<CValue>, Box<CValue>),
+    //Stores into a variable declared by the Cir::Scope around the statement
+    Assign(String, Box<CValue>),
+    //The char of a string at an index, checked against the length under --safe which is
+    //when it has the position of the [ for the error
+    StringIndex(Box<CValue>, Box<CValue>, Option<Position>),
 }
 
 impl fmt::Display for CValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            CValue::NumLiteral(n) => write!(f, "{n}"),
-            CValue::StringLiteral(s) => write!(f, "StrLit(\"{s}\")"),
+            CValue::NumLiteral(n, ctype) => match ctype {
+                //Without the U anything past INT32_MAX would be a signed long in C
+                CType::Uint => write!(f, "{n}U"),
+                //-2147483648 is the negation of a literal that does not fit an int32_t
+                CType::Int if *n == i32::MIN as i128 => write!(f, "(-2147483647 - 1)"),
+                CType::Int => write!(f, "{n}"),
+                _ => unreachable!(),
+            },
+            CValue::StringLiteral(s) => write!(f, "StrLit(\"{}\")", escape_c_string(s)),
             CValue::Variable(name) => write!(f, "{name}"),
             CValue::Bool(val) => write!(f, "{val}"),
-            CValue::BinaryOp(lhs, op, rhs) => match &**lhs {
-                CValue::StringLiteral(_) => match op {
-                    Op::Add => write!(f, "string_concat(&gc, &{lhs}, &{rhs})"),
-                    _ => unreachable!(),
-                },
-                _ => {
-                    write!(f, "{lhs} ")?;
-                    match op {
-                        Op::Add => write!(f, "+")?,
-                        Op::Minus => write!(f, "-")?,
-                        Op::Mult => write!(f, "*")?,
-                        Op::Div => write!(f, "/")?,
-                        Op::Mod => write!(f, "%")?,
-                        Op::Equal => write!(f, "==")?,
-                        Op::NotEqual => write!(f, "!=")?,
-                        Op::And => write!(f, "&&")?,
-                        Op::Or => write!(f, "||")?,
-                        Op::LessThan => write!(f, "<")?,
-                        Op::LessThanEq => write!(f, "<=")?,
-                        Op::GreaterThan => write!(f, ">")?,
-                        Op::GreaterThanEq => write!(f, ">=")?,
-                    }
-                    write!(f, " {rhs}")
+            CValue::CharLiteral(c) => write!(f, "'{}'", escape_c_char(*c)),
+            //Debug keeps the decimal point so C reads it as a double
+            CValue::FloatLiteral(val) => write!(f, "{val:?}"),
+            //Compound literals give the operands an address even when they are calls
+            CValue::StringConcat(pieces) => match pieces.as_slice() {
+                [lhs, rhs] => write!(
+                    f,
+                    "string_concat(&gc, (string_t[]){{{lhs}}}, (string_t[]){{{rhs}}})"
+                ),
+                _ => write!(
+                    f,
+                    "string_concat_n(&gc, {}, {})",
+                    pieces.len(),
+                    pieces
+                        .iter()
+                        .map(|piece| piece.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            },
+            CValue::BinaryOp(lhs, op, rhs) => {
+                //Always parenthesized so C precedence can't regroup what the parser built
+                write!(f, "({lhs} ")?;
+                match op {
+                    Op::Add => write!(f, "+")?,
+                    Op::Minus => write!(f, "-")?,
+                    Op::Mult => write!(f, "*")?,
+                    Op::Div => write!(f, "/")?,
+                    Op::Mod => write!(f, "%")?,
+                    Op::Pow => unreachable!(),
+                    Op::Equal => write!(f, "==")?,
+                    Op::NotEqual => write!(f, "!=")?,
+                    Op::And => write!(f, "&&")?,
+                    Op::Or => write!(f, "||")?,
+                    Op::LessThan => write!(f, "<")?,
+                    Op::LessThanEq => write!(f, "<=")?,
+                    Op::GreaterThan => write!(f, ">")?,
+                    Op::GreaterThanEq => write!(f, ">=")?,
                 }
+                write!(f, " {rhs})")
+            }
+            CValue::CheckedOp(lhs, op, rhs, ctype, position) => {
+                let op = match op {
+                    Op::Add => "add",
+                    Op::Minus => "sub",
+                    Op::Mult => "mul",
+                    _ => unreachable!(),
+                };
+                let ctype = match ctype {
+                    CType::Int => "i32",
+                    CType::Uint => "u32",
+                    _ => unreachable!(),
+                };
+                let location =
+                    format!("{}:{}:{}", position.filename, position.row, position.column);
+                write!(f, "pseudo_checked_{op}_{ctype}({lhs}, {rhs}, {location:?})")
+            }
+            CValue::Pow(base, exponent, ctype) => {
+                let helper = match ctype {
+                    CType::Int => "pseudo_pow_i32",
+                    CType::Uint => "pseudo_pow_u32",
+                    CType::Float => "pow",
+                    _ => unreachable!(),
+                };
+                write!(f, "{helper}({base}, {exponent})")
+            }
+            CValue::ToString(value, ctype) => match ctype {
+                CType::Int => write!(f, "pseudo_int_to_str(&gc, {value})"),
+                CType::Uint => write!(f, "pseudo_nat_to_str(&gc, {value})"),
+                CType::Float => write!(f, "pseudo_float_to_str(&gc, {value})"),
+                CType::Bool => write!(f, "pseudo_bool_to_str({value})"),
+                CType::Char => write!(f, "pseudo_char_to_str(&gc, {value})"),
+                CType::String => write!(f, "{value}"),
+                CType::Void => unreachable!(),
+            },
+            CValue::Conditional(cond, then_value, else_value) => {
+                write!(f, "({cond} ? {then_value} : {else_value})")
+            }
+            CValue::Assign(name, value) => write!(f, "({name} = {value})"),
+            CValue::StringIndex(string, index, None) => write!(f, "{string}.data[{index}]"),
+            CValue::StringIndex(string, index, Some(position)) => {
+                let location =
+                    format!("{}:{}:{}", position.filename, position.row, position.column);
+                write!(f, "pseudo_string_at({string}, {index}, {location:?})")
+            }
+            CValue::UnaryOp(op, operand) => match op {
+                UnaryOp::Not => write!(f, "!{operand}"),
+                UnaryOp::Neg => write!(f, "(-{operand})"),
             },
             CValue::SubProgCall(name, args) => {
                 write!(
@@ -79,35 +228,94 @@ impl fmt::Display for CValue {
                         .join(", ")
                 )
             }
+            CValue::BuiltinCall(builtin, args) => match builtin {
+                Builtin::ReadLine => write!(f, "read_line(&gc)"),
+                Builtin::ReadInt => write!(f, "pseudo_read_int()"),
+                Builtin::ReadNat => write!(f, "pseudo_read_nat()"),
+                Builtin::Exit => write!(f, "pseudo_exit({})", args[0]),
+                Builtin::WriteHex => write!(f, "printf(\"%\" PRIx32, (uint32_t)({}))", args[0]),
+                Builtin::WriteBin => write!(f, "pseudo_print_bin({})", args[0]),
+                Builtin::Min => write!(f, "pseudo_min({}, {})", args[0], args[1]),
+                Builtin::Max => write!(f, "pseudo_max({}, {})", args[0], args[1]),
+                Builtin::Abs => write!(f, "pseudo_abs({})", args[0]),
+                Builtin::Args => write!(f, "(uint32_t)argc"),
+                Builtin::Arg => write!(f, "pseudo_arg(argc, argv, {})", args[0]),
+                //Needs the argument type, lowered to CValue::ToString instead
+                Builtin::ToString => unreachable!(),
+            },
         }
     }
 }
 
 #[derive(Debug)]
 pub enum Cir {
-    Write(CType, CValue),
+    Write(CType, CValue, bool),
     Return(CValue),
     SubProgDef {
         name: String,
         cparams: Vec<CParam>,
         return_type: CType,
-        stmts_cir: Vec<Cir>,
+        stmts_cir: Vec<AstNode<Cir>>,
+        noreturn: bool,
     },
     SubProgramCall(String, Vec<CValue>),
-    If(CValue, Vec<Cir>),
-    While(CValue, Vec<Cir>),
-    Else(Vec<Cir>),
+    BuiltinCall(Builtin, Vec<CValue>),
+    If(CValue, Vec<AstNode<Cir>>),
+    //The last part is the else of the loop, it runs when the loop ends without a break
+    While(
+        CValue,
+        Vec<AstNode<Cir>>,
+        Option<Vec<AstNode<Cir>>>,
+        Option<String>,
+    ),
+    //Carry the label of the loop they target like their Stmts counterparts
+    Break(Option<String>),
+    Continue(Option<String>),
+    Repeat(CValue, Vec<AstNode<Cir>>, Option<String>),
+    //The variable, its type, the first and the last value
+    For(
+        String,
+        CType,
+        CValue,
+        CValue,
+        Vec<AstNode<Cir>>,
+        Option<String>,
+    ),
+    DoWhile(CValue, Vec<AstNode<Cir>>, Option<String>),
+    Else(Vec<AstNode<Cir>>),
     VariableDef(String, CType, CValue, bool),
     VarAssign(String, CValue),
+    Discard(CValue),
+    //A C block, it holds the variables bound in a condition along with the statement
+    Scope(Vec<AstNode<Cir>>),
+    VariableDecl(String, CType),
 }
 
-pub struct CirGenerator {}
+pub struct CirGenerator {
+    checked: bool,
+    safe: bool,
+    entry: String,
+}
 
 impl CirGenerator {
-    pub fn new() -> CirGenerator {
-        CirGenerator {}
+    pub fn new(config: &Config) -> CirGenerator {
+        CirGenerator {
+            checked: config.checked,
+            safe: config.safe,
+            entry: config.entry.clone(),
+        }
+    }
+
+    //The entry point is named main in C so codegen gives it the argc and argv signature and
+    //the collector
+    fn c_subprogram_name(self: &Self, name: String) -> String {
+        if name == self.entry {
+            "main".to_string()
+        } else {
+            name
+        }
     }
-    pub fn generate_cir(self: &Self, ast: Vec<AstNode<Stmts>>) -> Vec<Cir> {
+    pub fn generate_cir(self: &Self, ast: Vec<AstNode<Stmts>>) -> Vec<AstNode<Cir>> {
         ast.into_iter()
             .map(|node| self.generate_stmt_cir(node))
             .collect()
@@ -119,55 +327,209 @@ impl CirGenerator {
             Type::String => CType::String,
             Type::Int => CType::Int,
             Type::Bool => CType::Bool,
+            Type::Float => CType::Float,
+            Type::Char => CType::Char,
             Type::Void => CType::Void,
             Type::Unknown => unreachable!(),
         }
     }
 
-    fn to_c_value(self: &Self, expr: Expr) -> CValue {
-        match expr {
+    fn to_c_value(self: &Self, expr: AstNode<Expr>) -> CValue {
+        match expr.value {
             Expr::String(str) => CValue::StringLiteral(str),
-            //TODO: Actually implement this
-            Expr::Number(num) => CValue::NumLiteral(num),
+            Expr::Number(num, _, num_type) => {
+                //Semantic analysis rejects literals that don't fit their type, so anything
+                //outside of it reaching this point would be silently truncated by C
+                let fits = match num_type {
+                    Type::Int => i32::try_from(num).is_ok(),
+                    Type::Nat => u32::try_from(num).is_ok(),
+                    _ => false,
+                };
+                assert!(
+                    fits,
+                    "number literal {num} of type {num_type:?} should have been rejected by semantic analysis"
+                );
+                CValue::NumLiteral(num, self.to_c_type(num_type))
+            }
             Expr::Bool(bool_val) => CValue::Bool(bool_val),
-            Expr::Binary { lhs, op, rhs } => CValue::BinaryOp(
-                Box::new(self.to_c_value(lhs.value)),
+            Expr::Char(c) => CValue::CharLiteral(c),
+            Expr::Index {
+                expr: string,
+                index,
+            } => CValue::StringIndex(
+                Box::new(self.to_c_value(*string)),
+                Box::new(self.to_c_value(*index)),
+                self.safe.then_some(expr.position),
+            ),
+            Expr::Float(num) => CValue::FloatLiteral(num),
+            Expr::Binary {
+                lhs,
                 op,
-                Box::new(self.to_c_value(rhs.value)),
+                rhs,
+                operand_type,
+            } => {
+                let lhs = Box::new(self.to_c_value(*lhs));
+                let rhs = Box::new(self.to_c_value(*rhs));
+                let is_arithmetic = matches!(op, Op::Add | Op::Minus | Op::Mult);
+                let is_integer = matches!(operand_type, Type::Int | Type::Nat);
+                if operand_type == Type::String {
+                    //a + b + c is (a + b) + c, the inner concatenation is flattened into this one
+                    let mut pieces = Vec::new();
+                    for piece in [*lhs, *rhs] {
+                        match piece {
+                            CValue::StringConcat(inner) => pieces.extend(inner),
+                            piece => pieces.push(piece),
+                        }
+                    }
+                    CValue::StringConcat(pieces)
+                } else if matches!(op, Op::Pow) {
+                    CValue::Pow(lhs, rhs, self.to_c_type(operand_type))
+                } else if self.checked && is_arithmetic && is_integer {
+                    CValue::CheckedOp(lhs, op, rhs, self.to_c_type(operand_type), expr.position)
+                } else {
+                    CValue::BinaryOp(lhs, op, rhs)
+                }
+            }
+            Expr::Unary { op, expr: operand } => {
+                CValue::UnaryOp(op, Box::new(self.to_c_value(*operand)))
+            }
+            Expr::Conditional {
+                cond,
+                then_expr,
+                else_expr,
+            } => CValue::Conditional(
+                Box::new(self.to_c_value(*cond)),
+                Box::new(self.to_c_value(*then_expr)),
+                Box::new(self.to_c_value(*else_expr)),
             ),
+            Expr::Bind { name, expr, .. } => CValue::Assign(name, Box::new(self.to_c_value(*expr))),
             Expr::Variable(name) => CValue::Variable(name),
-            Expr::SubprogramCall { name, args } => {
+            Expr::SubprogramCall {
+                name,
+                args,
+                arg_types,
+            } => {
                 let mut cvalues = Vec::new();
                 for arg in args {
-                    cvalues.push(self.to_c_value(arg.value));
+                    cvalues.push(self.to_c_value(arg));
+                }
+                match Builtin::from_name(&name) {
+                    Some(Builtin::ToString) => {
+                        CValue::ToString(Box::new(cvalues.remove(0)), self.to_c_type(arg_types[0]))
+                    }
+                    Some(builtin) => CValue::BuiltinCall(builtin, cvalues),
+                    None => CValue::SubProgCall(self.c_subprogram_name(name), cvalues),
                 }
-                CValue::SubProgCall(name, cvalues)
             }
         }
     }
 
     fn invert_cvalue(self: &Self, cvalue: &mut CValue) {
-        match cvalue {
-            CValue::BinaryOp(_lhs, op, _rhs) => match op {
+        if let CValue::BinaryOp(_lhs, op, _rhs) = cvalue {
+            match op {
                 Op::LessThan => *op = Op::GreaterThan,
                 Op::LessThanEq => *op = Op::GreaterThanEq,
                 Op::GreaterThan => *op = Op::LessThan,
                 Op::GreaterThanEq => *op = Op::LessThanEq,
                 _ => {}
-            },
+            }
+        }
+    }
+
+    fn collect_bindings(self: &Self, expr: &Expr, bindings: &mut Vec<(String, CType)>) {
+        match expr {
+            Expr::Bind {
+                name,
+                expr,
+                var_type,
+            } => {
+                bindings.push((name.clone(), self.to_c_type(*var_type)));
+                self.collect_bindings(&expr.value, bindings);
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.collect_bindings(&lhs.value, bindings);
+                self.collect_bindings(&rhs.value, bindings);
+            }
+            Expr::Unary { expr, .. } => self.collect_bindings(&expr.value, bindings),
+            Expr::Index { expr, index } => {
+                self.collect_bindings(&expr.value, bindings);
+                self.collect_bindings(&index.value, bindings);
+            }
+            Expr::Conditional {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                self.collect_bindings(&cond.value, bindings);
+                self.collect_bindings(&then_expr.value, bindings);
+                self.collect_bindings(&else_expr.value, bindings);
+            }
+            Expr::SubprogramCall { args, .. } => {
+                for arg in args {
+                    self.collect_bindings(&arg.value, bindings);
+                }
+            }
             _ => {}
         }
     }
 
-    fn generate_stmt_cir(self: &Self, node: AstNode<Stmts>) -> Cir {
-        match node.value {
-            Stmts::Write { type_, expr } => {
-                let cvalue = self.to_c_value(expr.value);
+    //Nothing after a return in the same block can run and cc warns about it under -Wall,
+    //so the rest of the block is dropped
+    fn generate_block_cir(self: &Self, stmts: Vec<AstNode<Stmts>>) -> Vec<AstNode<Cir>> {
+        let mut stmts_cir = Vec::new();
+        let mut stmts = stmts.into_iter().peekable();
+        while let Some(stmt) = stmts.next() {
+            let mut bindings = Vec::new();
+            if let Stmts::If { expr, .. } | Stmts::While { expr, .. } = &stmt.value {
+                self.collect_bindings(&expr.value, &mut bindings);
+            }
+            let is_if = matches!(stmt.value, Stmts::If { .. });
+            let position = stmt.position.clone();
+            let mut stmt_cir = self.generate_stmt_cir(stmt);
+            //The bound variables are declared in a block of their own so they are gone after
+            //the statement, the else of an if has to go in there with it
+            if !bindings.is_empty() {
+                let mut scope = bindings
+                    .into_iter()
+                    .map(|(name, ctype)| AstNode {
+                        value: Cir::VariableDecl(name, ctype),
+                        position: position.clone(),
+                    })
+                    .collect::<Vec<AstNode<Cir>>>();
+                scope.push(stmt_cir);
+                if is_if
+                    && let Some(else_stmt) =
+                        stmts.next_if(|next| matches!(next.value, Stmts::Else(_)))
+                {
+                    scope.push(self.generate_stmt_cir(else_stmt));
+                }
+                stmt_cir = AstNode {
+                    value: Cir::Scope(scope),
+                    position,
+                };
+            }
+            let returns = matches!(stmt_cir.value, Cir::Return(_));
+            stmts_cir.push(stmt_cir);
+            if returns {
+                break;
+            }
+        }
+        stmts_cir
+    }
+
+    fn generate_stmt_cir(self: &Self, node: AstNode<Stmts>) -> AstNode<Cir> {
+        let value = match node.value {
+            Stmts::Write {
+                type_,
+                expr,
+                newline,
+            } => {
+                let cvalue = self.to_c_value(expr);
                 let ctype = self.to_c_type(type_);
-                Cir::Write(ctype, cvalue)
+                Cir::Write(ctype, cvalue, newline)
             }
             Stmts::Return { expr, .. } => {
-                let cvalue = self.to_c_value(expr.value);
+                let cvalue = self.to_c_value(expr);
                 Cir::Return(cvalue)
             }
             Stmts::SubProgramDef {
@@ -175,12 +537,11 @@ impl CirGenerator {
                 return_type,
                 stmts,
                 params,
+                noreturn,
+                ..
             } => {
                 let return_type = self.to_c_type(return_type);
-                let mut stmts_cir = Vec::new();
-                for stmt in stmts {
-                    stmts_cir.push(self.generate_stmt_cir(stmt));
-                }
+                let stmts_cir = self.generate_block_cir(stmts);
                 let mut cparams = Vec::new();
                 for param in params {
                     cparams.push(CParam {
@@ -189,36 +550,63 @@ impl CirGenerator {
                     })
                 }
                 Cir::SubProgDef {
-                    name,
+                    name: self.c_subprogram_name(name),
                     cparams,
                     return_type,
                     stmts_cir,
+                    noreturn,
                 }
             }
-            Stmts::If { expr, stmts } => {
-                let cvalue = self.to_c_value(expr.value);
-                let mut stmts_cir = Vec::new();
-                for stmt in stmts {
-                    stmts_cir.push(self.generate_stmt_cir(stmt));
-                }
+            Stmts::If { expr, stmts, .. } => {
+                let cvalue = self.to_c_value(expr);
+                let stmts_cir = self.generate_block_cir(stmts);
                 Cir::If(cvalue, stmts_cir)
             }
-            Stmts::While { expr, stmts } => {
-                let cvalue = self.to_c_value(expr.value);
-                let mut stmts_cir = Vec::new();
-                for stmt in stmts {
-                    stmts_cir.push(self.generate_stmt_cir(stmt));
-                }
-                Cir::While(cvalue, stmts_cir)
+            Stmts::While {
+                expr,
+                stmts,
+                else_stmts,
+                label,
+                ..
+            } => {
+                let cvalue = self.to_c_value(expr);
+                let stmts_cir = self.generate_block_cir(stmts);
+                let else_cir = else_stmts.map(|stmts| self.generate_block_cir(stmts));
+                Cir::While(cvalue, stmts_cir, else_cir, label)
             }
-            Stmts::Until { expr, stmts } => {
-                let mut cvalue = self.to_c_value(expr.value);
+            Stmts::Break(label) => Cir::Break(label),
+            Stmts::Continue(label) => Cir::Continue(label),
+            Stmts::Until { expr, stmts, label } => {
+                let mut cvalue = self.to_c_value(expr);
                 self.invert_cvalue(&mut cvalue);
-                let mut stmts_cir = Vec::new();
-                for stmt in stmts {
-                    stmts_cir.push(self.generate_stmt_cir(stmt));
-                }
-                Cir::While(cvalue, stmts_cir)
+                let stmts_cir = self.generate_block_cir(stmts);
+                Cir::While(cvalue, stmts_cir, None, label)
+            }
+            Stmts::Repeat {
+                count,
+                stmts,
+                label,
+            } => {
+                let cvalue = self.to_c_value(count);
+                let stmts_cir = self.generate_block_cir(stmts);
+                Cir::Repeat(cvalue, stmts_cir, label)
+            }
+            Stmts::For {
+                var,
+                var_type,
+                start,
+                end,
+                stmts,
+                label,
+            } => {
+                let start = self.to_c_value(start);
+                let end = self.to_c_value(end);
+                let stmts_cir = self.generate_block_cir(stmts);
+                Cir::For(var, self.to_c_type(var_type), start, end, stmts_cir, label)
+            }
+            Stmts::DoWhile { stmts, expr, label } => {
+                let stmts_cir = self.generate_block_cir(stmts);
+                Cir::DoWhile(self.to_c_value(expr), stmts_cir, label)
             }
             Stmts::Set {
                 name,
@@ -226,28 +614,36 @@ impl CirGenerator {
                 var_type,
                 mutable,
             } => {
-                let cvalue = self.to_c_value(expr.value);
-                let ctype = self.to_c_type(var_type);
-                Cir::VariableDef(name, ctype, cvalue, mutable)
+                let cvalue = self.to_c_value(expr);
+                if name == "_" {
+                    Cir::Discard(cvalue)
+                } else {
+                    let ctype = self.to_c_type(var_type);
+                    Cir::VariableDef(name, ctype, cvalue, mutable)
+                }
             }
             Stmts::Assign { name, expr } => {
-                let cvalue = self.to_c_value(expr.value);
+                let cvalue = self.to_c_value(expr);
                 Cir::VarAssign(name, cvalue)
             }
             Stmts::Else(stmts) => {
-                let mut stmts_cir = Vec::new();
-                for stmt in stmts {
-                    stmts_cir.push(self.generate_stmt_cir(stmt));
-                }
+                let stmts_cir = self.generate_block_cir(stmts);
                 Cir::Else(stmts_cir)
             }
             Stmts::SubProgramCall { name, args } => {
                 let mut cvalues = Vec::new();
                 for arg in args {
-                    cvalues.push(self.to_c_value(arg.value));
+                    cvalues.push(self.to_c_value(arg));
+                }
+                match Builtin::from_name(&name) {
+                    Some(builtin) => Cir::BuiltinCall(builtin, cvalues),
+                    None => Cir::SubProgramCall(self.c_subprogram_name(name), cvalues),
                 }
-                Cir::SubProgramCall(name, cvalues)
             }
+        };
+        AstNode {
+            value,
+            position: node.position,
         }
     }
 }
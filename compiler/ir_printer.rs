@@ -0,0 +1,139 @@
+use crate::ir::{CParam, CValue, Cir};
+use crate::parser::AstNode;
+use std::fmt::{self, Write};
+
+const INDENT: &str = "    ";
+
+fn join_values(values: &[CValue]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn join_params(cparams: &[CParam]) -> String {
+    cparams
+        .iter()
+        .map(|param| format!("{} {}", param.param_type, param.name))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn label_prefix(label: &Option<String>) -> String {
+    label
+        .as_ref()
+        .map(|label| format!("{label}: "))
+        .unwrap_or_default()
+}
+
+fn jump(keyword: &str, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("{keyword} {label}"),
+        None => keyword.to_string(),
+    }
+}
+
+//Prints the IR one node per line with blocks indented. Values are printed the way they end
+//up in the C code, so this is the generated program without the prelude and the C syntax
+pub struct IrPrinter {
+    sink: String,
+    indent: usize,
+}
+
+impl IrPrinter {
+    pub fn new() -> Self {
+        Self {
+            sink: String::new(),
+            indent: 0,
+        }
+    }
+
+    fn line(self: &mut Self, text: &str) -> fmt::Result {
+        writeln!(self.sink, "{}{text}", INDENT.repeat(self.indent))
+    }
+
+    fn block(self: &mut Self, header: &str, stmts: &[AstNode<Cir>]) -> fmt::Result {
+        self.line(header)?;
+        self.indent += 1;
+        self.print_stmts(stmts)?;
+        self.indent -= 1;
+        self.line("end")
+    }
+
+    fn print_stmts(self: &mut Self, stmts: &[AstNode<Cir>]) -> fmt::Result {
+        for stmt in stmts {
+            match &stmt.value {
+                Cir::Write(ctype, cvalue, newline) => {
+                    let keyword = if *newline { "writeln" } else { "write" };
+                    self.line(&format!("{keyword} {ctype} {cvalue}"))?;
+                }
+                Cir::Return(cvalue) => self.line(&format!("return {cvalue}"))?,
+                Cir::SubProgDef {
+                    name,
+                    cparams,
+                    return_type,
+                    stmts_cir,
+                    noreturn,
+                } => {
+                    let noreturn = if *noreturn { " noreturn" } else { "" };
+                    let header = format!(
+                        "subprogram {name}({}): {return_type}{noreturn}",
+                        join_params(cparams)
+                    );
+                    self.block(&header, stmts_cir)?;
+                }
+                Cir::SubProgramCall(name, args) => {
+                    self.line(&format!("call {name}({})", join_values(args)))?
+                }
+                Cir::BuiltinCall(builtin, args) => {
+                    let call = CValue::BuiltinCall(*builtin, args.clone());
+                    self.line(&format!("call {call}"))?;
+                }
+                Cir::If(cvalue, stmts_cir) => self.block(&format!("if {cvalue}"), stmts_cir)?,
+                Cir::Else(stmts_cir) => self.block("else", stmts_cir)?,
+                Cir::While(cvalue, stmts_cir, else_stmts, label) => {
+                    let header = format!("{}while {cvalue}", label_prefix(label));
+                    self.block(&header, stmts_cir)?;
+                    if let Some(else_stmts) = else_stmts {
+                        self.block("else", else_stmts)?;
+                    }
+                }
+                Cir::Break(label) => self.line(&jump("break", label))?,
+                Cir::Continue(label) => self.line(&jump("continue", label))?,
+                Cir::Repeat(count, stmts_cir, label) => {
+                    let header = format!("{}repeat {count} times", label_prefix(label));
+                    self.block(&header, stmts_cir)?;
+                }
+                Cir::For(var, var_type, start, end, stmts_cir, label) => {
+                    let header = format!(
+                        "{}for {var}: {var_type} := {start} to {end}",
+                        label_prefix(label)
+                    );
+                    self.block(&header, stmts_cir)?;
+                }
+                Cir::DoWhile(cvalue, stmts_cir, label) => {
+                    self.line(&format!("{}do", label_prefix(label)))?;
+                    self.indent += 1;
+                    self.print_stmts(stmts_cir)?;
+                    self.indent -= 1;
+                    self.line(&format!("end while {cvalue}"))?;
+                }
+                Cir::VariableDef(name, ctype, cvalue, mutable) => {
+                    let mutable = if *mutable { "mut " } else { "" };
+                    self.line(&format!("def {mutable}{name}: {ctype} = {cvalue}"))?;
+                }
+                Cir::VarAssign(name, cvalue) => self.line(&format!("{name} = {cvalue}"))?,
+                Cir::Discard(cvalue) => self.line(&format!("discard {cvalue}"))?,
+                Cir::Scope(stmts_cir) => self.block("scope", stmts_cir)?,
+                Cir::VariableDecl(name, ctype) => self.line(&format!("decl {name}: {ctype}"))?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn print_ir(mut self, ir: &[AstNode<Cir>]) -> Result<String, fmt::Error> {
+        self.print_stmts(ir)?;
+        Ok(self.sink)
+    }
+}
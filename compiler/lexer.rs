@@ -1,5 +1,22 @@
+use crate::config::Config;
 use std::fmt;
 
+//The type a number literal asks for with a trailing n or i
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum NumberSuffix {
+    Nat,
+    Int,
+}
+
+impl NumberSuffix {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NumberSuffix::Nat => "n",
+            NumberSuffix::Int => "i",
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenKind {
     //Symbols
@@ -7,6 +24,8 @@ pub enum TokenKind {
     Comma,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
     Semicolon,
     Equal,
     EqualEqual,
@@ -16,8 +35,14 @@ pub enum TokenKind {
     Plus,
     Slash,
     Percent,
+    Question,
     Star,
+    StarStar,
     Walrus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
     LessThan,
     GreaterThan,
     LessThanEq,
@@ -29,6 +54,7 @@ pub enum TokenKind {
     Start,
     Stop,
     Write,
+    WriteLn,
     Return,
     And,
     If,
@@ -36,6 +62,7 @@ pub enum TokenKind {
     End,
     Then,
     Do,
+    Noreturn,
     Or,
     Set,
     Mut,
@@ -43,18 +70,30 @@ pub enum TokenKind {
     False,
     While,
     Until,
+    Repeat,
+    Times,
+    For,
+    To,
+    Break,
+    Continue,
 
     //Types
     Int,
     Nat,
     Str,
     Bool,
+    Float,
+    Char,
 
-    Number(String),
+    Number(String, Option<NumberSuffix>),
+    FloatNumber(String),
     String(String),
+    CharLiteral(char),
     Ident(String),
     Eof,
     Illegal(char),
+    //The offending escape sequence in a string literal, backslash included
+    InvalidEscape(String),
 }
 
 impl fmt::Display for TokenKind {
@@ -63,23 +102,35 @@ impl fmt::Display for TokenKind {
             TokenKind::Colon => write!(f, ":"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Walrus => write!(f, ":="),
+            TokenKind::PlusEqual => write!(f, "+="),
+            TokenKind::MinusEqual => write!(f, "-="),
+            TokenKind::StarEqual => write!(f, "*="),
+            TokenKind::SlashEqual => write!(f, "/="),
             TokenKind::Minus => write!(f, "-"),
             TokenKind::LessThan => write!(f, "<"),
             TokenKind::LessThanEq => write!(f, "<="),
             TokenKind::GreaterThan => write!(f, ">"),
             TokenKind::GreaterThanEq => write!(f, ">="),
             TokenKind::Star => write!(f, "*"),
+            TokenKind::StarStar => write!(f, "**"),
             TokenKind::Percent => write!(f, "%"),
+            TokenKind::Question => write!(f, "?"),
             TokenKind::Slash => write!(f, "/"),
             TokenKind::Plus => write!(f, "+"),
             TokenKind::LParen => write!(f, "("),
             TokenKind::RParen => write!(f, ")"),
+            TokenKind::LBracket => write!(f, "["),
+            TokenKind::RBracket => write!(f, "]"),
             TokenKind::Semicolon => write!(f, ";"),
             TokenKind::Mut => write!(f, "mut"),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
             TokenKind::While => write!(f, "while"),
             TokenKind::Until => write!(f, "until"),
+            TokenKind::Repeat => write!(f, "repeat"),
+            TokenKind::For => write!(f, "for"),
+            TokenKind::To => write!(f, "to"),
+            TokenKind::Times => write!(f, "times"),
             TokenKind::And => write!(f, "and"),
             TokenKind::Func => write!(f, "func"),
             TokenKind::Not => write!(f, "!"),
@@ -91,22 +142,34 @@ impl fmt::Display for TokenKind {
             TokenKind::Else => write!(f, "else"),
             TokenKind::Then => write!(f, "then"),
             TokenKind::Do => write!(f, "do"),
+            TokenKind::Noreturn => write!(f, "noreturn"),
             TokenKind::End => write!(f, "end"),
             TokenKind::Proc => write!(f, "proc"),
             TokenKind::Start => write!(f, "start"),
             TokenKind::Stop => write!(f, "stop"),
             TokenKind::Set => write!(f, "set"),
             TokenKind::Write => write!(f, "write"),
+            TokenKind::WriteLn => write!(f, "writeln"),
             TokenKind::Return => write!(f, "return"),
+            TokenKind::Break => write!(f, "break"),
+            TokenKind::Continue => write!(f, "continue"),
             TokenKind::Int => write!(f, "int"),
             TokenKind::Nat => write!(f, "nat"),
             TokenKind::Str => write!(f, "string"),
             TokenKind::Bool => write!(f, "bool"),
-            TokenKind::Number(num) => write!(f, "number \"{num}\""),
+            TokenKind::Float => write!(f, "float"),
+            TokenKind::Char => write!(f, "char"),
+            TokenKind::Number(num, suffix) => {
+                let suffix = suffix.map_or("", |suffix| suffix.as_str());
+                write!(f, "number \"{num}{suffix}\"")
+            }
+            TokenKind::FloatNumber(num) => write!(f, "float \"{num}\""),
             TokenKind::String(string) => write!(f, "string \"{string}\""),
+            TokenKind::CharLiteral(c) => write!(f, "char {c:?}"),
             TokenKind::Ident(string) => write!(f, "identifier \"{string}\""),
             TokenKind::Eof => write!(f, "eof"),
             TokenKind::Illegal(tok) => write!(f, "illegal {tok}"),
+            TokenKind::InvalidEscape(escape) => write!(f, "invalid escape {escape}"),
         }
     }
 }
@@ -115,29 +178,143 @@ impl fmt::Display for TokenKind {
 pub struct Token {
     pub kind: TokenKind,
     pub filename: String,
+    //The visual column, where a tab advances to the next tab stop
     pub column: usize,
+    //The column counted in characters, a tab is one like any other
+    pub raw_column: usize,
     pub row: usize,
+    //Text of the /// comments right before this token
+    pub doc: Option<String>,
+    //A line break separates this token from the one before it
+    pub newline_before: bool,
+    //Row, visual and raw column of every character in the value of a string literal, an
+    //escape starts at its backslash
+    pub string_chars: Vec<(usize, usize, usize)>,
 }
 
+fn classify_ident(ident: &str) -> TokenKind {
+    match ident {
+        "start" => TokenKind::Start,
+        "stop" => TokenKind::Stop,
+        "string" => TokenKind::Str,
+        "set" => TokenKind::Set,
+        "mut" => TokenKind::Mut,
+        "func" => TokenKind::Func,
+        "proc" => TokenKind::Proc,
+        "if" => TokenKind::If,
+        "true" => TokenKind::True,
+        "false" => TokenKind::False,
+        "while" => TokenKind::While,
+        "until" => TokenKind::Until,
+        "repeat" => TokenKind::Repeat,
+        "for" => TokenKind::For,
+        "to" => TokenKind::To,
+        "times" => TokenKind::Times,
+        "else" => TokenKind::Else,
+        "or" => TokenKind::Or,
+        //The word form of "!", so it can't be used as a name
+        "not" => TokenKind::Not,
+        "and" => TokenKind::And,
+        "then" => TokenKind::Then,
+        "do" => TokenKind::Do,
+        "noreturn" => TokenKind::Noreturn,
+        "end" => TokenKind::End,
+        "int" => TokenKind::Int,
+        "nat" => TokenKind::Nat,
+        "bool" => TokenKind::Bool,
+        "float" => TokenKind::Float,
+        "char" => TokenKind::Char,
+        "write" => TokenKind::Write,
+        "writeln" => TokenKind::WriteLn,
+        "return" => TokenKind::Return,
+        "break" => TokenKind::Break,
+        "continue" => TokenKind::Continue,
+        _ => TokenKind::Ident(ident.to_string()),
+    }
+}
+
+//\x80 to \xff are not chars on their own, so they are kept as the private use chars U+F780
+//to U+F7FF and turned back into the byte when the literal is written out again
+const RAW_BYTE_BASE: u32 = 0xF700;
+
+fn raw_byte_char(byte: u8) -> char {
+    char::from_u32(RAW_BYTE_BASE + byte as u32).expect("U+F780 to U+F7FF are valid chars")
+}
+
+//The byte a \x80 to \xff escape stands for
+pub fn raw_byte(c: char) -> Option<u8> {
+    let byte = (c as u32).checked_sub(RAW_BYTE_BASE)?;
+    (0x80..=0xff).contains(&byte).then_some(byte as u8)
+}
+
+//A name spelled like a keyword has to be written between backticks to stay a name
+pub fn is_keyword(ident: &str) -> bool {
+    !matches!(classify_ident(ident), TokenKind::Ident(_))
+}
+
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
 pub struct Lexer {
     source: Vec<char>,
     pos: usize,
     read_pos: usize,
     row: usize,
+    //The raw column, tokens get their visual column from it and the line it is on
     column: usize,
+    //Index into source of the first character of every row
+    line_starts: Vec<usize>,
+    tab_width: usize,
     filename: String,
+    doc: Option<String>,
+    newline: bool,
 }
 
 impl Lexer {
-    pub fn new(filename: String, source: String) -> Self {
+    pub fn new(filename: String, source: String, config: &Config) -> Self {
         Self {
             source: source.chars().collect(),
             column: 0,
             row: 1,
+            line_starts: vec![0],
             pos: 0,
             read_pos: 0,
+            tab_width: config.tab_width,
             filename,
+            doc: None,
+            newline: false,
+        }
+    }
+
+    //Column reached after c, the \r of a \r\n line ending is part of the newline and takes up
+    //no column
+    fn next_column(&self, column: usize, c: char, next: char) -> usize {
+        match c {
+            '\t' => column + self.tab_width - column % self.tab_width,
+            '\r' if next == '\n' => column,
+            _ => column + 1,
+        }
+    }
+
+    //Visual column of the character raw_column characters into the line starting at line_start
+    fn visual_column(&self, line_start: usize, raw_column: usize) -> usize {
+        let mut column = 0;
+        for i in line_start..line_start + raw_column - 1 {
+            let next = self.source.get(i + 1).copied().unwrap_or('\0');
+            column = self.next_column(column, self.source[i], next);
         }
+        column + 1
+    }
+
+    //Sits one past the last character so errors about a missing token point at the end
+    pub fn eof_token(&self) -> Token {
+        let row = 1 + self.source.iter().filter(|&&c| c == '\n').count();
+        let line_start = self
+            .source
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |newline| newline + 1);
+        let raw_column = self.source.len() - line_start + 1;
+        self.token_at(TokenKind::Eof, row, line_start, raw_column)
     }
 
     fn peek(&self) -> char {
@@ -148,7 +325,7 @@ impl Lexer {
     }
 
     fn peek_next(&self) -> char {
-        if self.read_pos >= self.source.len() {
+        if self.read_pos + 1 >= self.source.len() {
             return '\0';
         }
         self.source[self.read_pos + 1]
@@ -158,9 +335,9 @@ impl Lexer {
         if self.read_pos >= self.source.len() {
             return '\0';
         }
-        self.column += 1;
         self.pos = self.read_pos;
         self.read_pos += 1;
+        self.column += 1;
         self.source[self.pos]
     }
 
@@ -173,146 +350,346 @@ impl Lexer {
                 }
                 '/' => {
                     if self.peek_next() == '/' {
-                        while self.peek() != '\n' {
-                            let _ = self.advance();
+                        let mut comment = String::new();
+                        //A comment on the last line may not have a newline after it
+                        while !matches!(self.peek(), '\n' | '\0') {
+                            comment.push(self.advance());
+                        }
+                        //Consecutive /// lines form one doc comment for the next token
+                        let comment = comment.trim_end_matches('\r');
+                        if let Some(text) = comment.strip_prefix("///") {
+                            let text = text.strip_prefix(' ').unwrap_or(text);
+                            match self.doc {
+                                Some(ref mut doc) => {
+                                    doc.push('\n');
+                                    doc.push_str(text);
+                                }
+                                None => self.doc = Some(text.to_string()),
+                            }
                         }
                     } else {
                         break;
                     }
                 }
                 '\n' => {
-                    self.column = 0;
-                    self.row += 1;
                     let _ = self.advance();
+                    self.start_line();
+                    self.newline = true;
                 }
                 _ => break,
             }
         }
     }
 
+    //Called right after a newline was consumed
+    fn start_line(&mut self) {
+        self.column = 0;
+        self.row += 1;
+        self.line_starts.push(self.read_pos);
+    }
+
     fn make_token(&self, token_kind: TokenKind, start_row: usize, start_col: usize) -> Token {
+        self.token_at(
+            token_kind,
+            start_row,
+            self.line_starts[start_row - 1],
+            start_col,
+        )
+    }
+
+    fn token_at(
+        &self,
+        token_kind: TokenKind,
+        row: usize,
+        line_start: usize,
+        raw_column: usize,
+    ) -> Token {
         Token {
             kind: token_kind,
-            column: start_col,
-            row: start_row,
+            column: self.visual_column(line_start, raw_column),
+            raw_column,
+            row,
             filename: self.filename.clone(),
+            doc: None,
+            newline_before: false,
+            string_chars: Vec::new(),
         }
     }
 
-    fn classify_ident(&self, ident: &str) -> TokenKind {
-        match ident {
-            "start" => TokenKind::Start,
-            "stop" => TokenKind::Stop,
-            "string" => TokenKind::Str,
-            "set" => TokenKind::Set,
-            "mut" => TokenKind::Mut,
-            "func" => TokenKind::Func,
-            "proc" => TokenKind::Proc,
-            "if" => TokenKind::If,
-            "true" => TokenKind::True,
-            "false" => TokenKind::False,
-            "while" => TokenKind::While,
-            "until" => TokenKind::Until,
-            "else" => TokenKind::Else,
-            "or" => TokenKind::Or,
-            "and" => TokenKind::And,
-            "then" => TokenKind::Then,
-            "do" => TokenKind::Do,
-            "end" => TokenKind::End,
-            "int" => TokenKind::Int,
-            "nat" => TokenKind::Nat,
-            "bool" => TokenKind::Bool,
-            "write" => TokenKind::Write,
-            "return" => TokenKind::Return,
-            _ => TokenKind::Ident(ident.to_string()),
+    //Unlike the Iterator impl this runs to completion and keeps the final Eof token
+    pub fn tokenize(mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        loop {
+            let token = self.next_token();
+            let is_eof = token.kind == TokenKind::Eof;
+            tokens.push(token);
+            if is_eof {
+                return tokens;
+            }
         }
     }
 
     fn next_token(&mut self) -> Token {
+        let mut token = self.scan_token();
+        token.doc = self.doc.take();
+        token.newline_before = std::mem::take(&mut self.newline);
+        token
+    }
+
+    //Called right after the backslash, gives back the escaped character or the sequence that
+    //could not be understood
+    fn scan_escape(&mut self) -> Result<char, String> {
+        let c = self.advance();
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            //Exactly two hex digits
+            'x' => {
+                let mut digits = String::new();
+                while digits.len() < 2 && self.peek().is_ascii_hexdigit() {
+                    digits.push(self.advance());
+                }
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte.is_ascii() => Ok(byte as char),
+                    Ok(byte) if digits.len() == 2 => Ok(raw_byte_char(byte)),
+                    _ => Err(format!("\\x{digits}")),
+                }
+            }
+            c => Err(format!("\\{c}")),
+        }
+    }
+
+    fn scan_token(&mut self) -> Token {
         self.skip_whitespace();
         let c = self.advance();
+        //Tokens are reported at the column of their first character
+        let start_row = self.row;
+        let start_col = self.column;
         match c {
             ':' => {
                 if self.peek() == '=' {
                     self.advance();
-                    self.make_token(TokenKind::Walrus, self.row, self.column)
+                    self.make_token(TokenKind::Walrus, start_row, start_col)
                 } else {
-                    self.make_token(TokenKind::Colon, self.row, self.column)
+                    self.make_token(TokenKind::Colon, start_row, start_col)
                 }
             }
             '>' => {
                 if self.peek() == '=' {
                     self.advance();
-                    self.make_token(TokenKind::GreaterThanEq, self.row, self.column)
+                    self.make_token(TokenKind::GreaterThanEq, start_row, start_col)
                 } else {
-                    self.make_token(TokenKind::GreaterThan, self.row, self.column)
+                    self.make_token(TokenKind::GreaterThan, start_row, start_col)
                 }
             }
             '<' => {
                 if self.peek() == '=' {
                     self.advance();
-                    self.make_token(TokenKind::LessThanEq, self.row, self.column)
+                    self.make_token(TokenKind::LessThanEq, start_row, start_col)
+                } else {
+                    self.make_token(TokenKind::LessThan, start_row, start_col)
+                }
+            }
+            ';' => self.make_token(TokenKind::Semicolon, start_row, start_col),
+            '/' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    self.make_token(TokenKind::SlashEqual, start_row, start_col)
                 } else {
-                    self.make_token(TokenKind::LessThan, self.row, self.column)
+                    self.make_token(TokenKind::Slash, start_row, start_col)
                 }
             }
-            ';' => self.make_token(TokenKind::Semicolon, self.row, self.column),
-            '/' => self.make_token(TokenKind::Slash, self.row, self.column),
-            '*' => self.make_token(TokenKind::Star, self.row, self.column),
-            '%' => self.make_token(TokenKind::Percent, self.row, self.column),
-            ',' => self.make_token(TokenKind::Comma, self.row, self.column),
-            '(' => self.make_token(TokenKind::LParen, self.row, self.column),
-            ')' => self.make_token(TokenKind::RParen, self.row, self.column),
+            '*' => {
+                if self.peek() == '*' {
+                    self.advance();
+                    self.make_token(TokenKind::StarStar, start_row, start_col)
+                } else if self.peek() == '=' {
+                    self.advance();
+                    self.make_token(TokenKind::StarEqual, start_row, start_col)
+                } else {
+                    self.make_token(TokenKind::Star, start_row, start_col)
+                }
+            }
+            '%' => self.make_token(TokenKind::Percent, start_row, start_col),
+            '?' => self.make_token(TokenKind::Question, start_row, start_col),
+            ',' => self.make_token(TokenKind::Comma, start_row, start_col),
+            '(' => self.make_token(TokenKind::LParen, start_row, start_col),
+            ')' => self.make_token(TokenKind::RParen, start_row, start_col),
+            '[' => self.make_token(TokenKind::LBracket, start_row, start_col),
+            ']' => self.make_token(TokenKind::RBracket, start_row, start_col),
             '!' => {
                 if self.peek() == '=' {
                     self.advance();
-                    self.make_token(TokenKind::NotEqual, self.row, self.column)
+                    self.make_token(TokenKind::NotEqual, start_row, start_col)
+                } else {
+                    self.make_token(TokenKind::Not, start_row, start_col)
+                }
+            }
+            '-' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    self.make_token(TokenKind::MinusEqual, start_row, start_col)
+                } else {
+                    self.make_token(TokenKind::Minus, start_row, start_col)
+                }
+            }
+            '+' => {
+                if self.peek() == '=' {
+                    self.advance();
+                    self.make_token(TokenKind::PlusEqual, start_row, start_col)
                 } else {
-                    self.make_token(TokenKind::Not, self.row, self.column)
+                    self.make_token(TokenKind::Plus, start_row, start_col)
                 }
             }
-            '-' => self.make_token(TokenKind::Minus, self.row, self.column),
-            '+' => self.make_token(TokenKind::Plus, self.row, self.column),
             '=' => {
                 if self.peek() == '=' {
                     self.advance();
-                    self.make_token(TokenKind::EqualEqual, self.row, self.column)
+                    self.make_token(TokenKind::EqualEqual, start_row, start_col)
                 } else {
-                    self.make_token(TokenKind::Equal, self.row, self.column)
+                    self.make_token(TokenKind::Equal, start_row, start_col)
                 }
             }
             '0'..='9' => {
-                let start_row = self.row;
-                let start_col = self.column - 1; // Already consumed the first num
                 let mut num = String::new();
                 num.push(c);
                 while self.peek().is_ascii_digit() {
                     num.push(self.advance());
                 }
-                self.make_token(TokenKind::Number(num), start_row, start_col)
+                //A dot only makes it a float when digits follow it
+                if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+                    num.push(self.advance());
+                    while self.peek().is_ascii_digit() {
+                        num.push(self.advance());
+                    }
+                    return self.make_token(TokenKind::FloatNumber(num), start_row, start_col);
+                }
+                //Only a lone n or i is a suffix, 5nat is still 5 followed by a name
+                let suffix = match self.peek() {
+                    'n' => Some(NumberSuffix::Nat),
+                    'i' => Some(NumberSuffix::Int),
+                    _ => None,
+                };
+                let suffix = suffix.filter(|_| {
+                    let next = self.peek_next();
+                    !next.is_alphanumeric() && next != '_'
+                });
+                if suffix.is_some() {
+                    self.advance();
+                }
+                self.make_token(TokenKind::Number(num, suffix), start_row, start_col)
             }
             'a'..='z' | 'A'..='Z' | '_' => {
-                let start_row = self.row;
-                let start_col = self.column;
                 let mut ident = String::new();
                 ident.push(c);
                 while self.peek().is_alphanumeric() || self.peek() == '_' {
                     ident.push(self.advance());
                 }
-                self.make_token(self.classify_ident(&ident), start_row, start_col)
+                self.make_token(classify_ident(&ident), start_row, start_col)
+            }
+            //`end` is the name end even though end is a keyword
+            '`' => {
+                let mut ident = String::new();
+                while self.peek().is_alphanumeric() || self.peek() == '_' {
+                    ident.push(self.advance());
+                }
+                let starts_like_name = ident
+                    .chars()
+                    .next()
+                    .is_some_and(|first| first.is_ascii_alphabetic() || first == '_');
+                if !starts_like_name || self.peek() != '`' {
+                    return self.make_token(TokenKind::Illegal('`'), start_row, start_col);
+                }
+                self.advance();
+                self.make_token(TokenKind::Ident(ident), start_row, start_col)
             }
-            '\0' => self.make_token(TokenKind::Eof, self.row, self.column),
+            '\0' => self.eof_token(),
+            //Exactly one character or escape between single quotes, anything else is reported
+            //as an illegal quote
+            '\'' => {
+                let value = match self.peek() {
+                    '\0' | '\n' | '\'' => None,
+                    '\\' => {
+                        self.advance();
+                        let (escape_row, escape_col) = (self.row, self.column);
+                        if self.peek() == '\0' {
+                            return self.make_token(TokenKind::Illegal('\''), start_row, start_col);
+                        }
+                        match self.scan_escape() {
+                            Ok(escaped) => Some(escaped),
+                            Err(escape) => {
+                                return self.make_token(
+                                    TokenKind::InvalidEscape(escape),
+                                    escape_row,
+                                    escape_col,
+                                );
+                            }
+                        }
+                    }
+                    _ => Some(self.advance()),
+                };
+                match value {
+                    Some(value) if self.peek() == '\'' => {
+                        self.advance();
+                        self.make_token(TokenKind::CharLiteral(value), start_row, start_col)
+                    }
+                    _ => self.make_token(TokenKind::Illegal('\''), start_row, start_col),
+                }
+            }
+            //Strings may span multiple lines, the newlines are kept in the value
             '"' => {
-                let start_row = self.row;
-                let start_col = self.column - 1; //This is because i'm skipping the quotes
                 let mut string = String::new();
+                let mut starts = Vec::new();
                 while self.peek() != '"' {
-                    string.push(self.advance());
+                    if self.peek() == '\0' {
+                        return self.make_token(TokenKind::Illegal('"'), start_row, start_col);
+                    }
+                    let c = self.advance();
+                    let start = (self.row, self.column);
+                    //A backslash right before the end of the file is left for the
+                    //unterminated string error
+                    if c == '\\' && self.peek() != '\0' {
+                        let (escape_row, escape_col) = (self.row, self.column);
+                        match self.scan_escape() {
+                            Ok(escaped) => {
+                                string.push(escaped);
+                                starts.push(start);
+                            }
+                            Err(escape) => {
+                                return self.make_token(
+                                    TokenKind::InvalidEscape(escape),
+                                    escape_row,
+                                    escape_col,
+                                );
+                            }
+                        }
+                        continue;
+                    }
+                    if c == '\r' && self.peek() == '\n' {
+                        continue;
+                    }
+                    starts.push(start);
+                    if c == '\n' {
+                        self.start_line();
+                    }
+                    string.push(c);
                 }
                 let _ = self.advance();
-                self.make_token(TokenKind::String(string), start_row, start_col)
+                let mut token = self.make_token(TokenKind::String(string), start_row, start_col);
+                token.string_chars = starts
+                    .into_iter()
+                    .map(|(row, raw_column)| {
+                        let column = self.visual_column(self.line_starts[row - 1], raw_column);
+                        (row, column, raw_column)
+                    })
+                    .collect();
+                token
             }
-            c => self.make_token(TokenKind::Illegal(c), self.row, self.column),
+            c => self.make_token(TokenKind::Illegal(c), start_row, start_col),
         }
     }
 }
@@ -0,0 +1,88 @@
+pub mod ast_printer;
+pub mod builtins;
+pub mod codegen;
+pub mod config;
+pub mod diagnostic;
+pub mod formatter;
+pub mod ir;
+pub mod ir_printer;
+pub mod lexer;
+pub mod parser;
+pub mod semantic;
+
+use codegen::CodeGen;
+use config::Config;
+use diagnostic::Diagnostic;
+use formatter::Formatter;
+use ir::CirGenerator;
+use lexer::Lexer;
+use parser::{AstNode, Parser, Position, Stmts, Type};
+use semantic::SemanticAnalyzer;
+use std::path::Path;
+
+pub fn parse(
+    source: &str,
+    filename: &str,
+    config: &Config,
+) -> Result<Vec<AstNode<Stmts>>, Vec<Diagnostic>> {
+    let lexer = Lexer::new(filename.to_string(), source.to_string(), config);
+    Parser::new(lexer, config)
+        .parse_program()
+        .map_err(|err| vec![err])
+}
+
+//Parses the source and prints it back in the canonical layout
+pub fn format_source(
+    source: &str,
+    filename: &str,
+    config: &Config,
+) -> Result<String, Vec<Diagnostic>> {
+    let ast = parse(source, filename, config)?;
+    Ok(Formatter::new()
+        .format_program(&ast)
+        .expect("Writing into a String should not fail"))
+}
+
+//Hands back the warnings when the program is fine, and the errors followed by the warnings
+//when it is not
+pub fn analyze(
+    ast: &mut [AstNode<Stmts>],
+    config: &Config,
+) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+    let mut analyzer = SemanticAnalyzer::new(config);
+    let result = analyzer.analyze_ast(ast);
+    let warnings = analyzer.take_warnings();
+    match result {
+        Ok(()) => Ok(warnings),
+        Err(errors) => Err(errors.into_iter().chain(warnings).collect()),
+    }
+}
+
+//The type the analyzer gave each set, write and return, for looking into type inference
+pub fn explain_types(
+    ast: &mut [AstNode<Stmts>],
+    config: &Config,
+) -> Result<Vec<(Position, Type)>, Vec<Diagnostic>> {
+    let mut analyzer = SemanticAnalyzer::new(config);
+    analyzer.analyze_ast(ast)?;
+    Ok(analyzer.take_inferred_types())
+}
+
+//The executable goes next to the source, named after it minus its last extension so that
+//my.test.pseudo becomes my.test. None when there is no extension to strip
+pub fn output_path(input_file_path: &str) -> Option<String> {
+    let path = Path::new(input_file_path);
+    path.extension()?;
+    Some(path.with_extension("").to_string_lossy().into_owned())
+}
+
+//Runs the whole pipeline on the source and returns the generated C code. Nothing in here
+//exits the process so it is safe to call from other programs
+pub fn compile(source: &str, filename: &str, config: &Config) -> Result<String, Vec<Diagnostic>> {
+    let mut ast = parse(source, filename, config)?;
+    analyze(&mut ast, config)?;
+    let ir = CirGenerator::new(config).generate_cir(ast);
+    Ok(CodeGen::new(config)
+        .generate_c_code(&ir)
+        .expect("Writing C code into a String should not fail"))
+}
@@ -1,13 +1,12 @@
-mod codegen;
-mod ir;
-mod lexer;
-mod parser;
-mod semantic;
-
-use crate::ir::CirGenerator;
-use codegen::CodeGen;
-use lexer::Lexer;
-use semantic::SemanticAnalyzer;
+use pseudo::ast_printer::AstPrinter;
+use pseudo::codegen::CodeGen;
+use pseudo::config::Config;
+use pseudo::diagnostic::{Diagnostic, Level};
+use pseudo::ir::CirGenerator;
+use pseudo::ir_printer::IrPrinter;
+use pseudo::lexer::Lexer;
+use std::io::{self, IsTerminal};
+use std::path::Path;
 use std::process::{self, Command};
 use std::{env, fs};
 
@@ -17,8 +16,41 @@ fn print_usage() {
     println!();
     println!("[OPTIONS:]");
     println!("--keep: This keeps the C code that was generated");
+    println!("--keep-ir: Like --keep but also writes the IR to <output>.ir");
     println!("--help: Prints this help message and exits");
-    println!("--optimize: builds an optimized version of the binary");
+    println!("--dump-ast: Prints the parsed AST and exits without compiling");
+    println!(
+        "--explain-types: Prints the type of every set, write and return as row:col: type and exits without compiling"
+    );
+    println!(
+        "--emit-ir=debug, --emit-ir=pretty: Prints the IR as its Rust structure or indented with C expressions and exits without compiling"
+    );
+    println!("--fmt: Prints the program back in the canonical layout and exits without compiling");
+    println!("--dump-tokens: Prints every token as row:col: kind and exits without compiling");
+    println!("-O0, -O1, -O2, -O3: Optimization level passed to cc (default -O0)");
+    println!("-O: Shorthand for -O2");
+    println!("--optimize: Shorthand for -O3");
+    println!("--checked: Exits with an error when int or nat arithmetic overflows");
+    println!("--safe: Exits with an error when a string index is out of bounds");
+    println!("--no-unused-warnings: Does not warn about subprograms that are never called");
+    println!(
+        "--no-empty-body-warnings: Does not warn about if, while and else bodies that are empty"
+    );
+    println!("--werror: Reports every warning as an error and fails the compilation");
+    println!(
+        "--c-std <std>: C standard passed to cc as -std=<std>, one of c99, c11, c17, c2x, gnu99, gnu11, gnu17 or gnu2x"
+    );
+    println!("-I, --include-dir <dir>: Adds a directory cc searches for headers, can be repeated");
+    println!("--relaxed-semicolons: Lets the end of a line end a statement instead of a semicolon");
+    println!(
+        "--no-prelude: Leaves out the includes and helpers, only writes <output>.c without compiling it"
+    );
+    println!("--entry <name>: Runs the subprogram name as the program instead of main");
+    println!("--max-errors <n>: Reports at most n errors, 0 reports all of them (default 20)");
+    println!("--tab-width <n>: Number of columns a tab advances in diagnostics (default 4)");
+    println!(
+        "--color=always, --color=never, --color=auto: Colors diagnostics, auto only does when stderr is a terminal (default auto)"
+    );
 }
 
 fn cli_error(msg: &str) -> ! {
@@ -32,33 +64,118 @@ fn compiler_error(msg: &str) -> ! {
     process::exit(1)
 }
 
+fn report_diagnostics(diagnostics: Vec<Diagnostic>, max_errors: usize, color: bool) -> ! {
+    let mut errors = 0;
+    for diagnostic in diagnostics {
+        if diagnostic.level == Level::Error {
+            errors += 1;
+            if max_errors != 0 && errors > max_errors {
+                continue;
+            }
+        }
+        eprintln!("{}", diagnostic.render(color));
+    }
+    if max_errors != 0 && errors > max_errors {
+        let hidden = errors - max_errors;
+        let plural = if hidden == 1 { "" } else { "s" };
+        eprintln!("... and {hidden} more error{plural}");
+    }
+    process::exit(1)
+}
+
+//Standards the generated code and the prelude are known to build with
+const C_STANDARDS: [&str; 8] = [
+    "c99", "c11", "c17", "c2x", "gnu99", "gnu11", "gnu17", "gnu2x",
+];
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+enum OptLevel {
+    #[default]
+    O0,
+    O1,
+    O2,
+    O3,
+}
+
+impl OptLevel {
+    fn cc_flag(&self) -> &'static str {
+        match self {
+            OptLevel::O0 => "-O0",
+            OptLevel::O1 => "-O1",
+            OptLevel::O2 => "-O2",
+            OptLevel::O3 => "-O3",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum IrFormat {
+    Debug,
+    Pretty,
+}
+
+#[derive(Clone, Copy, Default)]
+enum ColorChoice {
+    Always,
+    Never,
+    #[default]
+    Auto,
+}
+
+impl ColorChoice {
+    fn enabled(self: &Self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stderr().is_terminal(),
+        }
+    }
+}
+
 #[derive(Default)]
 struct CompilerCtx<'a> {
     c_file_path: &'a str,
     output_path: &'a str,
-    optimize: bool,
-    keep_ir_output: bool,
+    opt_level: OptLevel,
+    keep_c: bool,
+    keep_ir: bool,
+    dump_ast: bool,
+    dump_tokens: bool,
+    fmt: bool,
+    explain_types: bool,
+    emit_ir: Option<IrFormat>,
+    color: ColorChoice,
+    //Searched by cc before the libpseudo headers
+    include_dirs: Vec<String>,
+    //Left to the default of cc when None
+    c_std: Option<String>,
 }
 
 fn compile_c_code(ctx: CompilerCtx) {
-    let libpseudo_path;
-    match env::var_os("LIBPSEUDO") {
-        Some(val) => libpseudo_path = val,
+    let libpseudo_path = match env::var_os("LIBPSEUDO") {
+        Some(val) => val,
         None => compiler_error("LIBPSEUDO path variable not set"),
-    }
+    };
     let libpseudo_path = libpseudo_path.into_string().unwrap();
     let include_path = format!("{}/{}", libpseudo_path, "include");
     let lib_path = format!("{}/{}", libpseudo_path, "build/libpseudo.a");
-    let mut args = Vec::new();
-    if ctx.optimize {
-        args.push("-O3");
+    let std_flag = ctx.c_std.as_ref().map(|c_std| format!("-std={c_std}"));
+    let mut args = vec![ctx.opt_level.cc_flag()];
+    if let Some(std_flag) = &std_flag {
+        args.push(std_flag);
     }
     args.push(ctx.c_file_path);
     args.push("-o");
     args.push(ctx.output_path);
+    for include_dir in &ctx.include_dirs {
+        args.push("-I");
+        args.push(include_dir);
+    }
     args.push("-I");
     args.push(&include_path);
     args.push(&lib_path);
+    //pow for float ** lives in libm
+    args.push("-lm");
     let output = Command::new("cc")
         .args(args)
         .output()
@@ -66,22 +183,14 @@ fn compile_c_code(ctx: CompilerCtx) {
     if !output.status.success() {
         compiler_error(&format!("{}", String::from_utf8_lossy(&output.stderr)));
     }
-    if !ctx.keep_ir_output {
+    if !ctx.keep_c {
         fs::remove_file(ctx.c_file_path).expect("Failed to remove {c_filename}");
     }
 }
 
 fn get_output_path(input_file_path: &str) -> String {
-    let mut acc = Vec::new();
-    input_file_path.split("/").for_each(|s| acc.push(s));
-    let last = acc
-        .pop()
-        .unwrap()
-        .split(".")
-        .next()
-        .unwrap_or_else(|| cli_error("file doesn't have .pseudo extension"));
-    acc.push(last);
-    return acc.join("/");
+    pseudo::output_path(input_file_path)
+        .unwrap_or_else(|| cli_error("file doesn't have .pseudo extension"))
 }
 
 fn main() {
@@ -91,9 +200,11 @@ fn main() {
         cli_error("not enough arguements passed. See usage using --help");
     }
     let mut compiler_ctx = CompilerCtx::default();
+    let mut config = Config::default();
 
     let input_file_path = &args[0];
     let mut output_file_path = None;
+    let mut opt_level = None;
 
     let mut args = args[1..].iter();
     while let Some(arg) = args.next() {
@@ -102,20 +213,117 @@ fn main() {
                 Some(path) => output_file_path = Some(path.clone()),
                 None => cli_error("file output path should be specified after the -o flag"),
             },
+            "--tab-width" => match args.next().map(|width| width.parse::<usize>()) {
+                Some(Ok(width)) if width > 0 => config.tab_width = width,
+                _ => {
+                    cli_error("a positive tab width should be specified after the --tab-width flag")
+                }
+            },
+            "--max-errors" => match args.next().map(|max| max.parse::<usize>()) {
+                Some(Ok(max)) => config.max_errors = max,
+                _ => {
+                    cli_error("a number of errors should be specified after the --max-errors flag")
+                }
+            },
+            "--entry" => match args.next() {
+                Some(entry) => config.entry = entry.clone(),
+                None => cli_error("a subprogram name should be specified after the --entry flag"),
+            },
+            "--c-std" => match args.next() {
+                Some(c_std) if C_STANDARDS.contains(&c_std.as_str()) => {
+                    compiler_ctx.c_std = Some(c_std.clone())
+                }
+                Some(c_std) => cli_error(&format!(
+                    "unknown C standard {c_std}, expected one of {}",
+                    C_STANDARDS.join(", ")
+                )),
+                None => cli_error("a C standard should be specified after the --c-std flag"),
+            },
+            "-I" | "--include-dir" => match args.next() {
+                Some(dir) => compiler_ctx.include_dirs.push(dir.clone()),
+                None => cli_error(&format!(
+                    "a directory should be specified after the {arg} flag"
+                )),
+            },
             "--help" => {
                 print_usage();
             }
-            "--optimize" => {
-                compiler_ctx.optimize = true;
+            "-O" | "-O0" | "-O1" | "-O2" | "-O3" | "--optimize" => {
+                let level = match arg.as_str() {
+                    "-O0" => OptLevel::O0,
+                    "-O1" => OptLevel::O1,
+                    "-O" | "-O2" => OptLevel::O2,
+                    _ => OptLevel::O3,
+                };
+                if opt_level.is_some_and(|prev| prev != level) {
+                    cli_error(&format!(
+                        "conflicting optimization levels, {arg} was also passed"
+                    ));
+                }
+                opt_level = Some(level);
             }
             "--keep" => {
-                compiler_ctx.keep_ir_output = true;
+                compiler_ctx.keep_c = true;
+            }
+            "--keep-ir" => {
+                compiler_ctx.keep_c = true;
+                compiler_ctx.keep_ir = true;
+            }
+            "--dump-tokens" => {
+                compiler_ctx.dump_tokens = true;
+            }
+            "--dump-ast" => {
+                compiler_ctx.dump_ast = true;
+            }
+            "--explain-types" => {
+                compiler_ctx.explain_types = true;
+            }
+            "--emit-ir=debug" => {
+                compiler_ctx.emit_ir = Some(IrFormat::Debug);
+            }
+            "--emit-ir=pretty" => {
+                compiler_ctx.emit_ir = Some(IrFormat::Pretty);
+            }
+            "--color=always" => {
+                compiler_ctx.color = ColorChoice::Always;
+            }
+            "--color=never" => {
+                compiler_ctx.color = ColorChoice::Never;
+            }
+            "--color=auto" => {
+                compiler_ctx.color = ColorChoice::Auto;
+            }
+            "--fmt" => {
+                compiler_ctx.fmt = true;
+            }
+            "--checked" => {
+                config.checked = true;
+            }
+            "--safe" => {
+                config.safe = true;
+            }
+            "--no-unused-warnings" => {
+                config.unused_warnings = false;
+            }
+            "--no-empty-body-warnings" => {
+                config.empty_body_warnings = false;
+            }
+            "--relaxed-semicolons" => {
+                config.relaxed_semicolons = true;
+            }
+            "--no-prelude" => {
+                config.prelude = false;
+            }
+            "--werror" => {
+                config.werror = true;
             }
             arg => {
                 cli_error(&format!("Unknown arguement {arg} provided. See --help"));
             }
         }
     }
+    compiler_ctx.opt_level = opt_level.unwrap_or_default();
+    let color = compiler_ctx.color.enabled();
     //TODO: Actually process the file path
     if output_file_path.is_none() {
         output_file_path = Some(get_output_path(input_file_path));
@@ -125,16 +333,85 @@ fn main() {
         Err(err) => cli_error(&format!("could not open file: {input_file_path} {err}")),
     };
 
-    let lexer = Lexer::new(input_file_path.to_string(), source);
-    let mut parser = parser::Parser::new(lexer);
-    let mut ast = parser.parse_program();
-    let mut semanalyzer = SemanticAnalyzer::new();
-    semanalyzer.analyze_ast(&mut ast);
-    let mut codegen = CodeGen::new();
-    let ir_generator = CirGenerator::new();
+    if compiler_ctx.dump_tokens {
+        let lexer = Lexer::new(input_file_path.to_string(), source, &config);
+        for token in lexer.tokenize() {
+            println!("{}:{}: {}", token.row, token.column, token.kind);
+        }
+        return;
+    }
+    if compiler_ctx.fmt {
+        let formatted = pseudo::format_source(&source, input_file_path, &config).unwrap_or_else(
+            |diagnostics| report_diagnostics(diagnostics, config.max_errors, color),
+        );
+        print!("{formatted}");
+        return;
+    }
+    let mut ast = pseudo::parse(&source, input_file_path, &config)
+        .unwrap_or_else(|diagnostics| report_diagnostics(diagnostics, config.max_errors, color));
+    if compiler_ctx.dump_ast {
+        let dump = AstPrinter::new()
+            .print_ast(&ast)
+            .unwrap_or_else(|err| cli_error(&format!("could not print the ast {err}")));
+        print!("{dump}");
+        return;
+    }
+    if compiler_ctx.explain_types {
+        let inferred_types =
+            pseudo::explain_types(&mut ast, &config).unwrap_or_else(|diagnostics| {
+                report_diagnostics(diagnostics, config.max_errors, color)
+            });
+        for (position, inferred_type) in inferred_types {
+            println!("{}:{}: {inferred_type:?}", position.row, position.column);
+        }
+        return;
+    }
+    let warnings = pseudo::analyze(&mut ast, &config)
+        .unwrap_or_else(|diagnostics| report_diagnostics(diagnostics, config.max_errors, color));
+    for warning in warnings {
+        eprintln!("{}", warning.render(color));
+    }
+    let mut codegen = CodeGen::new(&config);
+    let ir_generator = CirGenerator::new(&config);
     let ir = ir_generator.generate_cir(ast);
+    if let Some(format) = compiler_ctx.emit_ir {
+        match format {
+            IrFormat::Debug => println!("{ir:#?}"),
+            IrFormat::Pretty => print!(
+                "{}",
+                IrPrinter::new()
+                    .print_ir(&ir)
+                    .unwrap_or_else(|err| cli_error(&format!("could not print the ir {err}")))
+            ),
+        }
+        return;
+    }
+    //-o may point into directories that do not exist yet. A bare file name has an empty
+    //parent which is the current directory
+    if let Some(parent) = output_file_path
+        .as_ref()
+        .and_then(|path| Path::new(path).parent())
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).unwrap_or_else(|err| {
+            cli_error(&format!(
+                "could not create output directory {}: {err}",
+                parent.display()
+            ))
+        });
+    }
+    if compiler_ctx.keep_ir {
+        let ir_file_path = format!(
+            "{}.ir",
+            output_file_path
+                .as_ref()
+                .expect("There should be a valid output file here")
+        );
+        fs::write(&ir_file_path, format!("{ir:#?}"))
+            .unwrap_or_else(|err| cli_error(&format!("could not write ir to file {err}")));
+    }
     let code = codegen
-        .generate_c_code(ir)
+        .generate_c_code(&ir)
         .unwrap_or_else(|err| cli_error(&format!("could not generate c code {err}")));
     let c_file_path = format!(
         "{}.c",
@@ -145,6 +422,10 @@ fn main() {
     fs::write(&c_file_path, code).unwrap_or_else(|err| {
         cli_error(&format!("could not write generated c code to file {err}"))
     });
+    //cc can not build the C without the declarations the prelude brings in
+    if !config.prelude {
+        return;
+    }
     compiler_ctx.c_file_path = &c_file_path;
     compiler_ctx.output_path = output_file_path
         .as_ref()
@@ -1,5 +1,8 @@
-use crate::lexer::{Lexer, Token, TokenKind};
+use crate::config::Config;
+use crate::diagnostic::Diagnostic;
+use crate::lexer::{Lexer, NumberSuffix, Token, TokenKind};
 use std::iter::Peekable;
+use std::num::IntErrorKind;
 
 //TODO: add all binary operators
 //TODO: use let some thing
@@ -17,6 +20,7 @@ pub enum Op {
     Div,
     Mult,
     Mod,
+    Pow,
     And,
     LessThan,
     GreaterThan,
@@ -36,6 +40,7 @@ impl Op {
             TokenKind::Slash => Op::Div,
             TokenKind::And => Op::And,
             TokenKind::Percent => Op::Mod,
+            TokenKind::StarStar => Op::Pow,
             TokenKind::LessThan => Op::LessThan,
             TokenKind::LessThanEq => Op::LessThanEq,
             TokenKind::GreaterThan => Op::GreaterThan,
@@ -43,22 +48,92 @@ impl Op {
             _ => unreachable!(),
         }
     }
+
+    //Higher binds tighter, None means the token is not a binary operator
+    pub fn precedence(token_kind: &TokenKind) -> Option<u8> {
+        match token_kind {
+            TokenKind::Or => Some(1),
+            TokenKind::And => Some(2),
+            TokenKind::EqualEqual
+            | TokenKind::NotEqual
+            | TokenKind::LessThan
+            | TokenKind::LessThanEq
+            | TokenKind::GreaterThan
+            | TokenKind::GreaterThanEq => Some(3),
+            TokenKind::Plus | TokenKind::Minus => Some(4),
+            TokenKind::Star | TokenKind::Slash | TokenKind::Percent => Some(5),
+            TokenKind::StarStar => Some(6),
+            _ => None,
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Op::Equal
+                | Op::NotEqual
+                | Op::LessThan
+                | Op::LessThanEq
+                | Op::GreaterThan
+                | Op::GreaterThanEq
+        )
+    }
+
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Op::And | Op::Or)
+    }
+
+    //2 ** 3 ** 2 is 2 ** (3 ** 2) like in maths
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Op::Pow)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UnaryOp {
+    Not,
+    Neg,
 }
 
 #[derive(Debug)]
 pub enum Expr {
-    Number(i128),
+    //The suffix overrides the type the context would give the literal
+    Number(i128, Option<NumberSuffix>, Type), //Type filled by sem analysis
+    Float(f64),
     String(String),
+    Char(char),
     Variable(String),
     Bool(bool),
     SubprogramCall {
         name: String,
         args: Vec<AstNode<Expr>>,
+        arg_types: Vec<Type>, //Filled by sem analysis
     },
     Binary {
         op: Op,
         lhs: Box<AstNode<Expr>>,
         rhs: Box<AstNode<Expr>>,
+        operand_type: Type, //Filled by sem analysis
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<AstNode<Expr>>,
+    },
+    Conditional {
+        cond: Box<AstNode<Expr>>,
+        then_expr: Box<AstNode<Expr>>,
+        else_expr: Box<AstNode<Expr>>,
+    },
+    //s[i], the char at a nat index of a string
+    Index {
+        expr: Box<AstNode<Expr>>,
+        index: Box<AstNode<Expr>>,
+    },
+    //(name := value) in an if or while condition, name lives until the end of the body
+    Bind {
+        name: String,
+        expr: Box<AstNode<Expr>>,
+        var_type: Type, //Filled by sem analysis
     },
 }
 
@@ -68,6 +143,8 @@ pub enum Type {
     String,
     Int,
     Bool,
+    Float,
+    Char,
     Void,
     Unknown,
 }
@@ -76,6 +153,9 @@ pub enum Type {
 pub struct Param {
     pub param_type: Type,
     pub name: String,
+    pub position: Position,
+    //Lets the body assign to it, the argument is copied so the caller never sees the change
+    pub mutable: bool,
 }
 
 #[derive(Debug)]
@@ -83,6 +163,7 @@ pub enum Stmts {
     Write {
         type_: Type, //Filled by sem analysis
         expr: AstNode<Expr>,
+        newline: bool,
     },
     Return {
         return_type: Type, //Filled by sem analysis
@@ -103,10 +184,15 @@ pub enum Stmts {
         return_type: Type,
         params: Vec<Param>,
         stmts: Vec<AstNode<Stmts>>,
+        doc: Option<String>,
+        //Marked noreturn, the body ends the program instead of returning
+        noreturn: bool,
     },
     If {
         expr: AstNode<Expr>,
         stmts: Vec<AstNode<Stmts>>,
+        //Where the then is, so an empty body can be pointed at
+        body_position: Position,
     },
     Else(Vec<AstNode<Stmts>>),
     SubProgramCall {
@@ -116,27 +202,64 @@ pub enum Stmts {
     While {
         expr: AstNode<Expr>,
         stmts: Vec<AstNode<Stmts>>,
+        //Where the do is, so an empty body can be pointed at
+        body_position: Position,
+        //Runs when the loop ends without a break
+        else_stmts: Option<Vec<AstNode<Stmts>>>,
+        label: Option<String>,
     },
+    //The label picks which enclosing loop to leave or continue, the innermost one without it
+    Break(Option<String>),
+    Continue(Option<String>),
     Until {
         expr: AstNode<Expr>,
         stmts: Vec<AstNode<Stmts>>,
+        label: Option<String>,
+    },
+    Repeat {
+        count: AstNode<Expr>,
+        stmts: Vec<AstNode<Stmts>>,
+        label: Option<String>,
+    },
+    //for var := start to end do, both bounds included
+    For {
+        var: String,
+        var_type: Type, //Filled by sem analysis
+        start: AstNode<Expr>,
+        end: AstNode<Expr>,
+        stmts: Vec<AstNode<Stmts>>,
+        label: Option<String>,
+    },
+    //do ... end while expr; runs the body before checking expr for the first time
+    DoWhile {
+        stmts: Vec<AstNode<Stmts>>,
+        expr: AstNode<Expr>,
+        label: Option<String>,
     },
 }
 
+//Bails out of the current parsing function with a diagnostic at the given token or position
 macro_rules! compiler_error {
     ($token:expr, $error_msg:expr) => {
-        eprintln!(
-            "{}:{}:{}: \x1b[31merror:\x1b[0m {}",
-            $token.filename, $token.row, $token.column, $error_msg
-        );
-        std::process::exit(1);
+        return Err(Diagnostic::error(
+            $error_msg.to_string(),
+            Position {
+                filename: $token.filename.clone(),
+                column: $token.column,
+                raw_column: $token.raw_column,
+                row: $token.row,
+            },
+        ))
     };
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Position {
     pub filename: String,
+    //Shown in diagnostics, tabs advance it to the next tab stop
     pub column: usize,
+    //Counted in characters from the start of the line
+    pub raw_column: usize,
     pub row: usize,
 }
 
@@ -145,6 +268,7 @@ impl Position {
         Position {
             filename: token.filename.clone(),
             column: token.column,
+            raw_column: token.raw_column,
             row: token.row,
         }
     }
@@ -160,19 +284,42 @@ pub struct Parser {
     lexer: Peekable<Lexer>,
     curr_token: Option<Token>,
     restore_token: Option<Token>,
+    //Where errors about running out of tokens are reported
+    eof: Token,
+    relaxed_semicolons: bool,
+    //Bindings are only allowed while parsing the condition of an if or a while
+    in_condition: bool,
+    //Where every character of the last string literal is in the source, for pointing at the
+    //braces of an interpolation
+    string_chars: Vec<(usize, usize, usize)>,
 }
 
 impl Parser {
-    pub fn new(lexer: Lexer) -> Self {
+    pub fn new(lexer: Lexer, config: &Config) -> Self {
         Self {
+            eof: lexer.eof_token(),
             lexer: lexer.peekable(),
             curr_token: None,
             restore_token: None,
+            relaxed_semicolons: config.relaxed_semicolons,
+            in_condition: false,
+            string_chars: Vec::new(),
         }
     }
 
-    pub fn parse_program(&mut self) -> Vec<AstNode<Stmts>> {
-        self.parse_statements()
+    pub fn parse_program(&mut self) -> Result<Vec<AstNode<Stmts>>, Diagnostic> {
+        let program = self.parse_statements()?;
+        //Reported here because the analyzer would have nothing to point at for main
+        if program.is_empty() {
+            let start = Position {
+                filename: self.eof.filename.clone(),
+                column: 1,
+                raw_column: 1,
+                row: 1,
+            };
+            compiler_error!(start, "empty source file: no statements found");
+        }
+        Ok(program)
     }
 
     fn curr_token(&self) -> &Token {
@@ -181,44 +328,101 @@ impl Parser {
             .expect("There should be a valid token in here always")
     }
 
-    fn get_and_expect(&mut self, token_kind: TokenKind) {
-        if let Some(token) = self.lexer.next() {
-            if token.kind != token_kind {
+    fn next_token(&mut self) -> Result<Option<Token>, Diagnostic> {
+        let Some(token) = self.lexer.next() else {
+            return Ok(None);
+        };
+        match token.kind {
+            TokenKind::Illegal('"') => {
+                compiler_error!(token, "unterminated string literal");
+            }
+            TokenKind::Illegal('\'') => {
+                compiler_error!(token, "a char literal holds exactly one character");
+            }
+            TokenKind::Illegal('`') => {
                 compiler_error!(
                     token,
-                    format!("expected {} but found {}", token_kind, token.kind)
+                    "a raw identifier is a name between backticks like `end`"
                 );
+            }
+            TokenKind::Illegal(c) => {
+                compiler_error!(token, format!("unexpected character '{c}'"));
+            }
+            TokenKind::InvalidEscape(ref escape) => {
+                let msg = match escape.strip_prefix("\\x") {
+                    Some(_) => format!("escape {escape} needs exactly two hex digits after \\x"),
+                    None => format!("unknown escape sequence {escape}"),
+                };
+                compiler_error!(token, msg);
+            }
+            _ => {}
+        }
+        Ok(Some(token))
+    }
+
+    fn get_and_expect(&mut self, token_kind: TokenKind) -> Result<(), Diagnostic> {
+        if let Some(token) = self.next_token()? {
+            if token.kind != token_kind {
+                //start/stop and then/do/end are easy to mix up so point at the right closer
+                match (&token_kind, &token.kind) {
+                    (TokenKind::Stop, TokenKind::End) => {
+                        compiler_error!(
+                            token,
+                            "expected stop but found end, subprograms are closed with 'stop', not 'end'"
+                        );
+                    }
+                    (TokenKind::End, TokenKind::Stop) => {
+                        compiler_error!(
+                            token,
+                            "expected end but found stop, blocks are closed with 'end', not 'stop'"
+                        );
+                    }
+                    _ => {
+                        compiler_error!(
+                            token,
+                            format!("expected {} but found {}", token_kind, token.kind)
+                        );
+                    }
+                }
             } else {
                 self.curr_token = Some(token);
+                Ok(())
             }
         } else {
-            compiler_error!(
-                self.curr_token(),
-                format!("expected {} but found eof", token_kind)
-            );
+            compiler_error!(self.eof, format!("expected {} but found eof", token_kind));
+        }
+    }
+
+    //With --relaxed-semicolons the end of the line also ends the statement
+    fn expect_semicolon(&mut self) -> Result<(), Diagnostic> {
+        if self.relaxed_semicolons
+            && self
+                .lexer
+                .peek()
+                .is_some_and(|token| token.newline_before && token.kind != TokenKind::Semicolon)
+        {
+            return Ok(());
         }
+        self.get_and_expect(TokenKind::Semicolon)
     }
 
-    fn get_maybe(&mut self, token_kind: TokenKind) -> bool {
+    fn get_maybe(&mut self, token_kind: TokenKind) -> Result<bool, Diagnostic> {
         if let Some(token) = self.lexer.peek() {
             if token.kind != token_kind {
-                return false;
+                Ok(false)
             } else {
-                self.get_and_expect(token_kind);
-                return true;
+                self.get_and_expect(token_kind)?;
+                Ok(true)
             }
         } else {
-            compiler_error!(
-                self.curr_token(),
-                format!("expected {} but found eof", token_kind)
-            );
+            compiler_error!(self.eof, format!("expected {} but found eof", token_kind));
         }
     }
 
-    fn get_and_return_ident(&mut self) -> String {
-        if let Some(token) = self.lexer.next() {
+    fn get_and_return_ident(&mut self) -> Result<String, Diagnostic> {
+        if let Some(token) = self.next_token()? {
             match token.kind {
-                TokenKind::Ident(name) => name,
+                TokenKind::Ident(name) => Ok(name),
                 _ => {
                     compiler_error!(
                         token,
@@ -227,231 +431,688 @@ impl Parser {
                 }
             }
         } else {
-            compiler_error!(self.curr_token(), "expected identifier but found eof");
+            compiler_error!(self.eof, "expected identifier but found eof");
         }
     }
 
     //TODO: ADD PRECEDENCE OF SOME KIND
-    fn parse_expression(&mut self) -> AstNode<Expr> {
-        if let Some(token) = self.lexer.next() {
-            let mut lhs = match token.kind {
-                TokenKind::Number(ref num) => {
-                    let num = num.parse::<i128>().unwrap_or_else(|err| {
+    fn parse_expression(&mut self) -> Result<AstNode<Expr>, Diagnostic> {
+        let cond = self.parse_binary(1)?;
+        self.parse_conditional_from(cond)
+    }
+
+    //cond ? a : b binds looser than every binary operator, a ? b : c ? d : e nests to the right
+    fn parse_conditional_from(&mut self, cond: AstNode<Expr>) -> Result<AstNode<Expr>, Diagnostic> {
+        if !self
+            .lexer
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Question)
+        {
+            return Ok(cond);
+        }
+        self.get_and_expect(TokenKind::Question)?;
+        let position = Position::from(self.curr_token());
+        let then_expr = self.parse_expression()?;
+        self.get_and_expect(TokenKind::Colon)?;
+        let else_expr = self.parse_expression()?;
+        Ok(AstNode {
+            value: Expr::Conditional {
+                cond: Box::new(cond),
+                then_expr: Box::new(then_expr),
+                else_expr: Box::new(else_expr),
+            },
+            position,
+        })
+    }
+
+    //Precedence climbing, every operator at or above min_precedence is folded into lhs
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<AstNode<Expr>, Diagnostic> {
+        let lhs = self.parse_primary()?;
+        self.parse_binary_from(lhs, min_precedence)
+    }
+
+    //Same as parse_binary for when the first operand has already been parsed
+    fn parse_binary_from(
+        &mut self,
+        mut lhs: AstNode<Expr>,
+        min_precedence: u8,
+    ) -> Result<AstNode<Expr>, Diagnostic> {
+        while let Some(precedence) = self
+            .lexer
+            .peek()
+            .and_then(|token| Op::precedence(&token.kind))
+        {
+            if precedence < min_precedence {
+                break;
+            }
+            let tok = self.next_token()?.unwrap();
+            //The node sits on its operator, the operands keep their own positions
+            let position = Position::from(&tok);
+            let op = Op::from(tok.kind);
+            //Left associative operators only let tighter ones into the rhs, right associative
+            //ones also take operators of their own precedence
+            let rhs_precedence = if op.is_right_associative() {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let rhs = self.parse_binary(rhs_precedence)?;
+            lhs = AstNode {
+                value: Expr::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                    operand_type: Type::Unknown,
+                },
+                position,
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_condition(&mut self) -> Result<AstNode<Expr>, Diagnostic> {
+        self.in_condition = true;
+        let expr = self.parse_expression();
+        self.in_condition = false;
+        expr
+    }
+
+    fn parse_primary(&mut self) -> Result<AstNode<Expr>, Diagnostic> {
+        let Some(token) = self.next_token()? else {
+            compiler_error!(self.eof, "expected expression but found eof");
+        };
+        self.parse_primary_from(token)
+    }
+
+    //Same as parse_primary for a token that has already been taken from the lexer
+    fn parse_primary_from(&mut self, token: Token) -> Result<AstNode<Expr>, Diagnostic> {
+        let node = match token.kind {
+            TokenKind::Number(ref num, suffix) => {
+                let num = match num.parse::<i128>() {
+                    Ok(num) => num,
+                    //Digits only ever overflow, the range of the actual type is checked later
+                    Err(err) if *err.kind() == IntErrorKind::PosOverflow => {
+                        compiler_error!(token, "integer literal is too large");
+                    }
+                    Err(err) => {
                         compiler_error!(
                             token,
-                            format!("could not parse {} as a  number because {err}", token.kind,)
+                            format!("could not parse {} as a  number because {err}", token.kind)
                         );
-                    });
-                    AstNode {
-                        value: Expr::Number(num),
-                        position: Position::from(&token),
                     }
-                }
-                TokenKind::String(ref str) => AstNode {
-                    value: Expr::String(str.clone()),
+                };
+                AstNode {
+                    value: Expr::Number(num, suffix, Type::Unknown),
                     position: Position::from(&token),
-                },
-                TokenKind::True => AstNode {
-                    value: Expr::Bool(true),
+                }
+            }
+            TokenKind::FloatNumber(ref num) => {
+                let num = match num.parse::<f64>() {
+                    Ok(num) => num,
+                    Err(err) => {
+                        compiler_error!(
+                            token,
+                            format!("could not parse {} as a float because {err}", token.kind)
+                        );
+                    }
+                };
+                AstNode {
+                    value: Expr::Float(num),
                     position: Position::from(&token),
-                },
-                TokenKind::False => AstNode {
-                    value: Expr::Bool(false),
+                }
+            }
+            //"a" "b" is read as "ab" like in C
+            TokenKind::String(ref str) => {
+                let mut string = str.clone();
+                self.string_chars = token.string_chars.clone();
+                while let Some(Token {
+                    kind: TokenKind::String(next),
+                    string_chars,
+                    ..
+                }) = self
+                    .lexer
+                    .next_if(|next| matches!(next.kind, TokenKind::String(_)))
+                {
+                    string.push_str(&next);
+                    self.string_chars.extend(string_chars);
+                }
+                AstNode {
+                    value: Expr::String(string),
                     position: Position::from(&token),
-                },
-                TokenKind::Ident(ref name) => {
-                    if let Some(token) = self.lexer.peek() {
-                        if token.kind == TokenKind::LParen {
-                            let position = Position::from(&token);
-                            self.get_and_expect(TokenKind::LParen);
-                            let args = self.parse_subprog_args();
-                            self.get_and_expect(TokenKind::RParen);
-                            AstNode {
-                                value: Expr::SubprogramCall {
-                                    name: name.to_string(),
-                                    args,
-                                },
-                                position,
-                            }
-                        } else {
-                            AstNode {
-                                value: Expr::Variable(name.clone()),
-                                position: Position::from(&token),
-                            }
-                        }
-                    } else {
-                        AstNode {
-                            value: Expr::Variable(name.clone()),
-                            position: Position::from(&token),
-                        }
-                    }
                 }
-                _ => {
+            }
+            TokenKind::CharLiteral(c) => AstNode {
+                value: Expr::Char(c),
+                position: Position::from(&token),
+            },
+            TokenKind::True => AstNode {
+                value: Expr::Bool(true),
+                position: Position::from(&token),
+            },
+            TokenKind::False => AstNode {
+                value: Expr::Bool(false),
+                position: Position::from(&token),
+            },
+            //Parentheses only ever hold a binding
+            TokenKind::LParen => {
+                let name = self.get_and_return_ident()?;
+                self.get_and_expect(TokenKind::Walrus)?;
+                if !self.in_condition {
                     compiler_error!(
                         token,
-                        format!("could not parse {} as an expression", token.kind)
+                        "a (name := value) binding can only be used in an if or while condition"
                     );
                 }
-            };
-            while let Some(token) = self.lexer.peek() {
-                match token.kind {
-                    TokenKind::Equal
-                    | TokenKind::EqualEqual
-                    | TokenKind::NotEqual
-                    | TokenKind::Or
-                    | TokenKind::LessThan
-                    | TokenKind::LessThanEq
-                    | TokenKind::GreaterThan
-                    | TokenKind::GreaterThanEq
-                    | TokenKind::And
-                    | TokenKind::Plus
-                    | TokenKind::Minus
-                    | TokenKind::Star
-                    | TokenKind::Slash
-                    | TokenKind::Percent => {
-                        let position = Position::from(&token);
-                        let tok = self.lexer.next().unwrap();
-                        let op = Op::from(tok.kind);
-                        let rhs = self.parse_expression();
-                        lhs = AstNode {
-                            value: Expr::Binary {
-                                op,
-                                lhs: Box::new(lhs),
-                                rhs: Box::new(rhs),
-                            },
-                            position,
-                        }
+                let expr = self.parse_expression()?;
+                self.get_and_expect(TokenKind::RParen)?;
+                AstNode {
+                    value: Expr::Bind {
+                        name,
+                        expr: Box::new(expr),
+                        var_type: Type::Unknown,
+                    },
+                    position: Position::from(&token),
+                }
+            }
+            TokenKind::Ident(ref name) => {
+                let position = Position::from(&token);
+                if self
+                    .lexer
+                    .peek()
+                    .is_some_and(|next| next.kind == TokenKind::LParen)
+                {
+                    self.get_and_expect(TokenKind::LParen)?;
+                    let args = self.parse_subprog_args()?;
+                    self.get_and_expect(TokenKind::RParen)?;
+                    AstNode {
+                        value: Expr::SubprogramCall {
+                            name: name.to_string(),
+                            args,
+                            arg_types: Vec::new(),
+                        },
+                        position,
+                    }
+                } else {
+                    AstNode {
+                        value: Expr::Variable(name.clone()),
+                        position,
                     }
-                    _ => break,
                 }
             }
-            lhs
-        } else {
-            compiler_error!(self.curr_token(), "expected expression but found none");
+            TokenKind::Not => {
+                let operand = self.parse_primary()?;
+                AstNode {
+                    value: Expr::Unary {
+                        op: UnaryOp::Not,
+                        expr: Box::new(operand),
+                    },
+                    position: Position::from(&token),
+                }
+            }
+            TokenKind::Minus => {
+                let operand = self.parse_primary()?;
+                //Fold negative literals so they are range checked as a whole
+                let value = match operand.value {
+                    Expr::Number(num, suffix, num_type) => Expr::Number(-num, suffix, num_type),
+                    Expr::Float(num) => Expr::Float(-num),
+                    _ => Expr::Unary {
+                        op: UnaryOp::Neg,
+                        expr: Box::new(operand),
+                    },
+                };
+                AstNode {
+                    value,
+                    position: Position::from(&token),
+                }
+            }
+            _ => {
+                compiler_error!(
+                    token,
+                    format!("could not parse {} as an expression", token.kind)
+                );
+            }
+        };
+        self.parse_index_from(node)
+    }
+
+    //Indexing binds tighter than any operator, the position is the one of the [
+    fn parse_index_from(&mut self, mut node: AstNode<Expr>) -> Result<AstNode<Expr>, Diagnostic> {
+        while self.get_maybe(TokenKind::LBracket)? {
+            let position = Position::from(self.curr_token());
+            let index = self.parse_expression()?;
+            self.get_and_expect(TokenKind::RBracket)?;
+            node = AstNode {
+                value: Expr::Index {
+                    expr: Box::new(node),
+                    index: Box::new(index),
+                },
+                position,
+            };
         }
+        Ok(node)
     }
 
-    #[allow(unreachable_code)]
-    fn parse_type(&mut self) -> Type {
-        if let Some(token) = self.lexer.next() {
+    fn parse_type(&mut self) -> Result<Type, Diagnostic> {
+        if let Some(token) = self.next_token()? {
             match token.kind {
-                TokenKind::Int => Type::Int,
-                TokenKind::Nat => Type::Nat,
-                TokenKind::Str => Type::String,
-                TokenKind::Bool => Type::Bool,
+                TokenKind::Int => Ok(Type::Int),
+                TokenKind::Nat => Ok(Type::Nat),
+                TokenKind::Float => Ok(Type::Float),
+                TokenKind::Str => Ok(Type::String),
+                TokenKind::Bool => Ok(Type::Bool),
+                TokenKind::Char => Ok(Type::Char),
                 _ => {
                     compiler_error!(token, format!("unknown type \"{}\"", token.kind));
-                    unreachable!();
                 }
             }
         } else {
-            compiler_error!(self.curr_token(), "expected a type but found eof");
-            unreachable!();
+            compiler_error!(self.eof, "expected a type but found eof");
         }
     }
 
-    fn parse_return_stmt(&mut self) -> Stmts {
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::Semicolon);
-        Stmts::Return {
+    fn parse_return_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let expr = self.parse_expression()?;
+        self.expect_semicolon()?;
+        Ok(Stmts::Return {
             return_type: Type::Unknown,
             expr,
+        })
+    }
+
+    fn parse_if_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let expr = self.parse_condition()?;
+        self.get_and_expect(TokenKind::Then)?;
+        let body_position = Position::from(self.curr_token());
+        let stmts = self.parse_statements()?;
+        if !self.get_maybe(TokenKind::Else)? {
+            self.get_and_expect(TokenKind::End)?;
         }
+        Ok(Stmts::If {
+            expr,
+            stmts,
+            body_position,
+        })
     }
 
-    fn parse_if_stmt(&mut self) -> Stmts {
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::Then);
-        let stmts = self.parse_statements();
-        if !self.get_maybe(TokenKind::Else) {
-            self.get_and_expect(TokenKind::End);
+    fn parse_while_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let expr = self.parse_condition()?;
+        self.get_and_expect(TokenKind::Do)?;
+        let body_position = Position::from(self.curr_token());
+        let stmts = self.parse_statements()?;
+        let else_stmts = if self.get_maybe(TokenKind::Else)? {
+            //Unlike the else of an if this one belongs to the loop, so it is not left for the
+            //enclosing block to parse
+            self.restore_token = None;
+            let else_stmts = self.parse_statements()?;
+            self.get_and_expect(TokenKind::End)?;
+            Some(else_stmts)
+        } else {
+            self.get_and_expect(TokenKind::End)?;
+            None
+        };
+        Ok(Stmts::While {
+            expr,
+            stmts,
+            body_position,
+            else_stmts,
+            label: None,
+        })
+    }
+
+    fn parse_until_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let expr = self.parse_expression()?;
+        self.get_and_expect(TokenKind::Do)?;
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::End)?;
+        Ok(Stmts::Until {
+            expr,
+            stmts,
+            label: None,
+        })
+    }
+
+    fn parse_repeat_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let count = self.parse_expression()?;
+        self.get_and_expect(TokenKind::Times)?;
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::End)?;
+        Ok(Stmts::Repeat {
+            count,
+            stmts,
+            label: None,
+        })
+    }
+
+    fn parse_do_while_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::End)?;
+        self.get_and_expect(TokenKind::While)?;
+        let expr = self.parse_expression()?;
+        self.expect_semicolon()?;
+        Ok(Stmts::DoWhile {
+            stmts,
+            expr,
+            label: None,
+        })
+    }
+
+    //`outer: while ...` names the loop so a break or continue in a nested loop can target it
+    fn parse_labeled_loop(&mut self) -> Result<Stmts, Diagnostic> {
+        let label = match self.curr_token().kind {
+            TokenKind::Ident(ref name) => name.clone(),
+            _ => unreachable!(),
+        };
+        self.get_and_expect(TokenKind::Colon)?;
+        let Some(token) = self.next_token()? else {
+            compiler_error!(
+                self.eof,
+                format!("expected a loop after the label {label} but found eof")
+            );
+        };
+        let mut stmt = match token.kind {
+            TokenKind::While => self.parse_while_stmt()?,
+            TokenKind::Until => self.parse_until_stmt()?,
+            TokenKind::Repeat => self.parse_repeat_stmt()?,
+            TokenKind::For => self.parse_for_stmt()?,
+            TokenKind::Do => self.parse_do_while_stmt()?,
+            _ => {
+                compiler_error!(
+                    token,
+                    format!(
+                        "expected a loop after the label {label} but found {}",
+                        token.kind
+                    )
+                );
+            }
+        };
+        match &mut stmt {
+            Stmts::While { label: slot, .. }
+            | Stmts::Until { label: slot, .. }
+            | Stmts::Repeat { label: slot, .. }
+            | Stmts::For { label: slot, .. }
+            | Stmts::DoWhile { label: slot, .. } => *slot = Some(label),
+            _ => unreachable!(),
         }
-        Stmts::If { expr, stmts }
+        Ok(stmt)
     }
 
-    fn parse_while_stmt(&mut self) -> Stmts {
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::Do);
-        let stmts = self.parse_statements();
-        self.get_and_expect(TokenKind::End);
-        Stmts::While { expr, stmts }
+    //break and continue, with the label of the loop they target if there is one
+    fn parse_loop_jump_label(&mut self) -> Result<Option<String>, Diagnostic> {
+        //A name on the next line starts the next statement when line breaks end statements
+        let label = if self.lexer.peek().is_some_and(|token| {
+            matches!(token.kind, TokenKind::Ident(_))
+                && !(self.relaxed_semicolons && token.newline_before)
+        }) {
+            Some(self.get_and_return_ident()?)
+        } else {
+            None
+        };
+        self.expect_semicolon()?;
+        Ok(label)
     }
 
-    fn parse_until_stmt(&mut self) -> Stmts {
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::Do);
-        let stmts = self.parse_statements();
-        self.get_and_expect(TokenKind::End);
-        Stmts::Until { expr, stmts }
+    fn parse_for_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let var = self.get_and_return_ident()?;
+        self.get_and_expect(TokenKind::Walrus)?;
+        let start = self.parse_expression()?;
+        self.get_and_expect(TokenKind::To)?;
+        let end = self.parse_expression()?;
+        self.get_and_expect(TokenKind::Do)?;
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::End)?;
+        Ok(Stmts::For {
+            var,
+            var_type: Type::Unknown,
+            start,
+            end,
+            stmts,
+            label: None,
+        })
     }
 
     //TODO: Ensure it is within an if block
-    fn parse_else_stmt(&mut self) -> Stmts {
-        let stmts = self.parse_statements();
-        self.get_and_expect(TokenKind::End);
-        Stmts::Else(stmts)
+    fn parse_else_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        //"else if" continues the chain instead of opening a block that needs its own end,
+        //the else block then holds the if and whatever else follows it
+        if let Some(token) = self.lexer.peek()
+            && token.kind == TokenKind::If
+        {
+            let position = Position::from(token);
+            self.get_and_expect(TokenKind::If)?;
+            let mut stmts = vec![AstNode {
+                value: self.parse_if_stmt()?,
+                position,
+            }];
+            if let Some(else_token) = self.restore_token.take() {
+                let position = Position::from(&else_token);
+                self.curr_token = Some(else_token);
+                stmts.push(AstNode {
+                    value: self.parse_else_stmt()?,
+                    position,
+                });
+            }
+            return Ok(Stmts::Else(stmts));
+        }
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::End)?;
+        Ok(Stmts::Else(stmts))
     }
 
-    fn parse_write_stmt(&mut self) -> Stmts {
-        self.get_and_expect(TokenKind::LParen);
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::RParen);
-        self.get_and_expect(TokenKind::Semicolon);
-        Stmts::Write {
-            type_: Type::Unknown,
-            expr,
+    fn parse_write_stmt(
+        &mut self,
+        newline: bool,
+        position: Position,
+    ) -> Result<Vec<AstNode<Stmts>>, Diagnostic> {
+        self.get_and_expect(TokenKind::LParen)?;
+        let expr = self.parse_expression()?;
+        //Several values are written with one write each or with interpolation
+        if self.get_maybe(TokenKind::Comma)? {
+            let keyword = if newline { "writeln" } else { "write" };
+            compiler_error!(
+                self.curr_token(),
+                format!("{keyword} takes a single argument")
+            );
+        }
+        self.get_and_expect(TokenKind::RParen)?;
+        self.expect_semicolon()?;
+        //Only a string literal that is the whole argument is interpolated
+        if let Expr::String(ref template) = expr.value
+            && template.contains(['{', '}'])
+        {
+            let pieces = self.split_interpolation(template, &expr.position)?;
+            let last = pieces.len() - 1;
+            return Ok(pieces
+                .into_iter()
+                .enumerate()
+                .map(|(i, piece)| AstNode {
+                    value: Stmts::Write {
+                        type_: Type::Unknown,
+                        expr: piece,
+                        newline: newline && i == last,
+                    },
+                    position: position.clone(),
+                })
+                .collect());
+        }
+        Ok(vec![AstNode {
+            value: Stmts::Write {
+                type_: Type::Unknown,
+                expr,
+                newline,
+            },
+            position,
+        }])
+    }
+
+    // "x = {x}" is split into its literal pieces and its interpolated variables.
+    // "{{" and "}}" are escapes for literal braces
+    fn split_interpolation(
+        &mut self,
+        template: &str,
+        expr_position: &Position,
+    ) -> Result<Vec<AstNode<Expr>>, Diagnostic> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let sources = std::mem::take(&mut self.string_chars);
+        let brace_position = |offset: usize| {
+            let (row, column, raw_column) = sources[offset];
+            Position {
+                row,
+                column,
+                raw_column,
+                ..expr_position.clone()
+            }
+        };
+        let mut chars = template.chars().enumerate().peekable();
+        while let Some((offset, c)) = chars.next() {
+            let next = chars.peek().map(|(_, next)| *next);
+            match c {
+                '{' if next == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if next == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let position = brace_position(offset);
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) if c.is_alphanumeric() || c == '_' => name.push(c),
+                            _ => {
+                                compiler_error!(
+                                    position,
+                                    format!("unterminated interpolation in string \"{template}\"")
+                                );
+                            }
+                        }
+                    }
+                    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                        compiler_error!(
+                            position,
+                            format!("expected a variable name inside {{}} but found \"{name}\"")
+                        );
+                    }
+                    if !literal.is_empty() {
+                        parts.push(AstNode {
+                            value: Expr::String(std::mem::take(&mut literal)),
+                            position: expr_position.clone(),
+                        });
+                    }
+                    parts.push(AstNode {
+                        value: Expr::Variable(name),
+                        position,
+                    });
+                }
+                '}' => {
+                    compiler_error!(
+                        brace_position(offset),
+                        format!("unmatched }} in string \"{template}\", use }}}} for a literal }}")
+                    );
+                }
+                c => literal.push(c),
+            }
         }
+        if !literal.is_empty() || parts.is_empty() {
+            parts.push(AstNode {
+                value: Expr::String(literal),
+                position: expr_position.clone(),
+            });
+        }
+        Ok(parts)
     }
 
-    fn parse_set_stmt(&mut self) -> Stmts {
+    fn parse_set_stmt(&mut self) -> Result<Stmts, Diagnostic> {
         let mut mutable = false;
         let mut var_type = Type::Unknown;
-        if self.get_maybe(TokenKind::Mut) {
+        if self.get_maybe(TokenKind::Mut)? {
             mutable = true;
         }
-        let name = self.get_and_return_ident();
-        if !self.get_maybe(TokenKind::Walrus) {
-            self.get_and_expect(TokenKind::Colon);
-            var_type = self.parse_type();
-            self.get_and_expect(TokenKind::Equal);
+        let name = self.get_and_return_ident()?;
+        if !self.get_maybe(TokenKind::Walrus)? {
+            if !self.get_maybe(TokenKind::Colon)? {
+                let token = self.next_token()?.expect("get_maybe already reported eof");
+                compiler_error!(
+                    token,
+                    format!(
+                        "expected ':=' or ': <type> =' in set statement but found {}",
+                        token.kind
+                    )
+                );
+            }
+            var_type = self.parse_type()?;
+            self.get_and_expect(TokenKind::Equal)?;
         }
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::Semicolon);
+        let expr = self.parse_expression()?;
+        self.expect_semicolon()?;
 
-        Stmts::Set {
+        Ok(Stmts::Set {
             name,
             var_type,
             expr,
             mutable,
-        }
+        })
     }
-    fn parse_varassign_stmt(&mut self) -> Stmts {
+    fn parse_varassign_stmt(&mut self) -> Result<Stmts, Diagnostic> {
         let name = match self.curr_token().kind {
             TokenKind::Ident(ref name) => name.clone(),
             _ => unreachable!(),
         };
-        self.get_and_expect(TokenKind::Equal);
-        let expr = self.parse_expression();
-        self.get_and_expect(TokenKind::Semicolon);
-        Stmts::Assign { name, expr }
+        let name_position = Position::from(self.curr_token());
+        let token = self
+            .next_token()?
+            .expect("The caller peeked the assignment operator");
+        //x += 1 is sugar for x = x + 1, the addition sits on the += like any other operator
+        let op = match token.kind {
+            TokenKind::Equal => None,
+            TokenKind::PlusEqual => Some(Op::Add),
+            TokenKind::MinusEqual => Some(Op::Minus),
+            TokenKind::StarEqual => Some(Op::Mult),
+            TokenKind::SlashEqual => Some(Op::Div),
+            _ => unreachable!(),
+        };
+        let mut expr = self.parse_expression()?;
+        if let Some(op) = op {
+            expr = AstNode {
+                value: Expr::Binary {
+                    op,
+                    lhs: Box::new(AstNode {
+                        value: Expr::Variable(name.clone()),
+                        position: name_position,
+                    }),
+                    rhs: Box::new(expr),
+                    operand_type: Type::Unknown,
+                },
+                position: Position::from(&token),
+            };
+        }
+        self.expect_semicolon()?;
+        Ok(Stmts::Assign { name, expr })
     }
 
-    fn parse_params(&mut self) -> Vec<Param> {
+    fn parse_params(&mut self) -> Result<Vec<Param>, Diagnostic> {
         let mut params = Vec::new();
         while let Some(token) = self.lexer.peek() {
             match token.kind {
                 TokenKind::RParen => break,
-                TokenKind::Ident(_) => {
-                    let name = self.get_and_return_ident();
-                    self.get_and_expect(TokenKind::Colon);
-                    let param_type = self.parse_type();
+                TokenKind::Ident(_) | TokenKind::Mut => {
+                    let position = Position::from(token);
+                    let mutable = self.get_maybe(TokenKind::Mut)?;
+                    let name = self.get_and_return_ident()?;
+                    self.get_and_expect(TokenKind::Colon)?;
+                    let param_type = self.parse_type()?;
                     params.push(Param {
                         name: name.clone(),
                         param_type,
+                        position,
+                        mutable,
                     });
                 }
                 TokenKind::Comma => {
-                    self.get_and_expect(TokenKind::Comma);
+                    self.get_and_expect(TokenKind::Comma)?;
                     continue;
                 }
                 _ => {
@@ -462,181 +1123,286 @@ impl Parser {
                 }
             }
         }
-        params
-    }
-
-    fn parse_func_stmt(&mut self) -> Stmts {
-        let name = self.get_and_return_ident();
-        self.get_and_expect(TokenKind::LParen);
-        let params = self.parse_params();
-        self.get_and_expect(TokenKind::RParen);
-        self.get_and_expect(TokenKind::Colon);
-        let return_type = self.parse_type();
-        self.get_and_expect(TokenKind::Start);
-        let stmts = self.parse_statements();
-        self.get_and_expect(TokenKind::Stop);
-        Stmts::SubProgramDef {
+        Ok(params)
+    }
+
+    //A forgotten start shows up as the first statement of the body
+    fn expect_body_start(&mut self, kind: &str) -> Result<(), Diagnostic> {
+        if let Some(token) = self.lexer.peek()
+            && matches!(
+                token.kind,
+                TokenKind::Set
+                    | TokenKind::Write
+                    | TokenKind::WriteLn
+                    | TokenKind::Return
+                    | TokenKind::If
+                    | TokenKind::While
+                    | TokenKind::Until
+                    | TokenKind::Repeat
+                    | TokenKind::For
+                    | TokenKind::Do
+                    | TokenKind::Break
+                    | TokenKind::Continue
+            )
+        {
+            compiler_error!(
+                token,
+                format!(
+                    "expected start but found {}, {kind} bodies must begin with 'start'",
+                    token.kind
+                )
+            );
+        }
+        self.get_and_expect(TokenKind::Start)
+    }
+
+    //The optional noreturn between the signature and start
+    fn parse_noreturn(&mut self) -> bool {
+        self.lexer
+            .next_if(|next| next.kind == TokenKind::Noreturn)
+            .is_some()
+    }
+
+    fn parse_func_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let doc = self.curr_token().doc.clone();
+        let name = self.get_and_return_ident()?;
+        self.get_and_expect(TokenKind::LParen)?;
+        let params = self.parse_params()?;
+        self.get_and_expect(TokenKind::RParen)?;
+        self.get_and_expect(TokenKind::Colon)?;
+        let return_type = self.parse_type()?;
+        let noreturn = self.parse_noreturn();
+        self.expect_body_start("function")?;
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::Stop)?;
+        Ok(Stmts::SubProgramDef {
             name,
             return_type,
             stmts,
             params,
-        }
+            doc,
+            noreturn,
+        })
     }
 
-    fn parse_subprog_args(&mut self) -> Vec<AstNode<Expr>> {
+    fn parse_subprog_args(&mut self) -> Result<Vec<AstNode<Expr>>, Diagnostic> {
         let mut args = Vec::new();
         while let Some(token) = self.lexer.peek() {
             if token.kind == TokenKind::RParen {
                 break;
             } else if token.kind == TokenKind::Comma {
-                self.get_and_expect(TokenKind::Comma);
+                self.get_and_expect(TokenKind::Comma)?;
                 continue;
             }
-            let expr = self.parse_expression();
+            let expr = self.parse_expression()?;
             args.push(expr)
         }
-        args
-    }
-
-    fn parse_proc_stmt(&mut self) -> Stmts {
-        let name = self.get_and_return_ident();
-        self.get_and_expect(TokenKind::LParen);
-        let params = self.parse_params();
-        self.get_and_expect(TokenKind::RParen);
-        self.get_and_expect(TokenKind::Start);
-        let stmts = self.parse_statements();
-        self.get_and_expect(TokenKind::Stop);
-        Stmts::SubProgramDef {
+        Ok(args)
+    }
+
+    fn parse_proc_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let doc = self.curr_token().doc.clone();
+        let name = self.get_and_return_ident()?;
+        self.get_and_expect(TokenKind::LParen)?;
+        let params = self.parse_params()?;
+        self.get_and_expect(TokenKind::RParen)?;
+        let noreturn = self.parse_noreturn();
+        self.expect_body_start("procedure")?;
+        let stmts = self.parse_statements()?;
+        self.get_and_expect(TokenKind::Stop)?;
+        Ok(Stmts::SubProgramDef {
             name,
             return_type: Type::Void,
             stmts,
             params,
-        }
+            doc,
+            noreturn,
+        })
     }
 
-    fn parse_subprogcall_stmt(&mut self) -> Stmts {
-        let name = match self.curr_token().kind {
-            TokenKind::Ident(ref name) => name.clone(),
-            _ => unreachable!(),
-        };
-        self.get_and_expect(TokenKind::LParen);
-        let args = self.parse_subprog_args();
-        self.get_and_expect(TokenKind::RParen);
-        self.get_and_expect(TokenKind::Semicolon);
-        Stmts::SubProgramCall {
-            name: name.to_string(),
-            args,
+    //An assignment, or an expression that is only evaluated for its side effect which means
+    //it has to be a call
+    fn parse_expression_stmt(&mut self) -> Result<Stmts, Diagnostic> {
+        let token = self.curr_token().clone();
+        if matches!(token.kind, TokenKind::Ident(_))
+            && self.lexer.peek().is_some_and(|next| {
+                matches!(
+                    next.kind,
+                    TokenKind::Equal
+                        | TokenKind::PlusEqual
+                        | TokenKind::MinusEqual
+                        | TokenKind::StarEqual
+                        | TokenKind::SlashEqual
+                )
+            })
+        {
+            return self.parse_varassign_stmt();
+        }
+        let lhs = self.parse_primary_from(token)?;
+        let expr = self.parse_binary_from(lhs, 1)?;
+        let expr = self.parse_conditional_from(expr)?;
+        self.expect_semicolon()?;
+        match expr.value {
+            Expr::SubprogramCall { name, args, .. } => Ok(Stmts::SubProgramCall { name, args }),
+            _ => {
+                compiler_error!(expr.position, "this expression has no effect");
+            }
         }
     }
 
-    fn parse_statements(&mut self) -> Vec<AstNode<Stmts>> {
+    fn parse_statements(&mut self) -> Result<Vec<AstNode<Stmts>>, Diagnostic> {
         let mut statements = Vec::new();
         while let Some(token) = self.lexer.peek() {
-            match token.kind {
-                TokenKind::Stop | TokenKind::End => break,
-                TokenKind::Else => {
-                    self.restore_token = Some(token.clone());
-                    break;
+            //A pending else has already been consumed, so the end after an empty else body
+            //must not stop it from being parsed
+            if self.restore_token.is_none() {
+                match token.kind {
+                    TokenKind::Stop | TokenKind::End => break,
+                    TokenKind::Else => {
+                        self.restore_token = Some(token.clone());
+                        break;
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
             if self.restore_token.is_none() {
-                let token = self.lexer.next().unwrap();
+                let token = self.next_token()?.unwrap();
                 self.curr_token = Some(token);
             } else {
                 self.curr_token = self.restore_token.clone();
                 self.restore_token = None;
             }
+            if matches!(self.curr_token().kind, TokenKind::Ident(_))
+                && self
+                    .lexer
+                    .peek()
+                    .is_some_and(|token| token.kind == TokenKind::Colon)
+            {
+                let position = Position::from(self.curr_token());
+                statements.push(AstNode {
+                    value: self.parse_labeled_loop()?,
+                    position,
+                });
+                continue;
+            }
             match self.curr_token().kind {
                 TokenKind::Write => {
-                    let position = Position::from(&self.curr_token());
-                    statements.push(AstNode {
-                        value: self.parse_write_stmt(),
-                        position,
-                    });
+                    let position = Position::from(self.curr_token());
+                    statements.extend(self.parse_write_stmt(false, position)?);
+                }
+                TokenKind::WriteLn => {
+                    let position = Position::from(self.curr_token());
+                    statements.extend(self.parse_write_stmt(true, position)?);
                 }
                 TokenKind::Func => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_func_stmt(),
+                        value: self.parse_func_stmt()?,
                         position,
                     });
                 }
                 TokenKind::Proc => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_proc_stmt(),
+                        value: self.parse_proc_stmt()?,
                         position,
                     });
                 }
                 TokenKind::Return => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_return_stmt(),
+                        value: self.parse_return_stmt()?,
                         position,
                     });
                 }
                 TokenKind::If => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_if_stmt(),
+                        value: self.parse_if_stmt()?,
                         position,
                     });
                 }
                 TokenKind::Else => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_else_stmt(),
+                        value: self.parse_else_stmt()?,
                         position,
                     });
                 }
                 TokenKind::Set => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_set_stmt(),
+                        value: self.parse_set_stmt()?,
                         position,
                     });
                 }
                 TokenKind::While => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
+                    statements.push(AstNode {
+                        value: self.parse_while_stmt()?,
+                        position,
+                    });
+                }
+                TokenKind::Break => {
+                    let position = Position::from(self.curr_token());
+                    statements.push(AstNode {
+                        value: Stmts::Break(self.parse_loop_jump_label()?),
+                        position,
+                    });
+                }
+                TokenKind::Continue => {
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_while_stmt(),
+                        value: Stmts::Continue(self.parse_loop_jump_label()?),
                         position,
                     });
                 }
                 TokenKind::Until => {
-                    let position = Position::from(&self.curr_token());
+                    let position = Position::from(self.curr_token());
                     statements.push(AstNode {
-                        value: self.parse_until_stmt(),
+                        value: self.parse_until_stmt()?,
                         position,
                     });
                 }
-                TokenKind::Ident(_) => {
-                    if let Some(token) = self.lexer.peek() {
-                        match token.kind {
-                            TokenKind::LParen => {
-                                let position = Position::from(&token);
-                                statements.push(AstNode {
-                                    value: self.parse_subprogcall_stmt(),
-                                    position,
-                                });
-                            }
-                            TokenKind::Equal => {
-                                let position = Position::from(&token);
-                                statements.push(AstNode {
-                                    value: self.parse_varassign_stmt(),
-                                    position,
-                                })
-                            }
-                            //TODO: CHANGE THIS ERROR
-                            _ => {
-                                compiler_error!(
-                                    self.curr_token(),
-                                    format!("unknown identifier {}", self.curr_token().kind)
-                                );
-                            }
-                        }
-                    }
+                TokenKind::Repeat => {
+                    let position = Position::from(self.curr_token());
+                    statements.push(AstNode {
+                        value: self.parse_repeat_stmt()?,
+                        position,
+                    });
+                }
+                TokenKind::For => {
+                    let position = Position::from(self.curr_token());
+                    statements.push(AstNode {
+                        value: self.parse_for_stmt()?,
+                        position,
+                    });
+                }
+                TokenKind::Do => {
+                    let position = Position::from(self.curr_token());
+                    statements.push(AstNode {
+                        value: self.parse_do_while_stmt()?,
+                        position,
+                    });
+                }
+                TokenKind::Ident(_)
+                | TokenKind::Number(..)
+                | TokenKind::FloatNumber(_)
+                | TokenKind::String(_)
+                | TokenKind::CharLiteral(_)
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::Not
+                | TokenKind::Minus => {
+                    //Calls and assignments sit on the token after their first one, the ( or =
+                    let position = match self.lexer.peek() {
+                        Some(token) => Position::from(token),
+                        None => Position::from(&self.eof),
+                    };
+                    statements.push(AstNode {
+                        value: self.parse_expression_stmt()?,
+                        position,
+                    });
                 }
                 _ => {
                     compiler_error!(
@@ -646,6 +1412,6 @@ impl Parser {
                 }
             }
         }
-        statements
+        Ok(statements)
     }
 }
@@ -1,9 +1,15 @@
-use crate::parser::{AstNode, Expr, Position, Stmts, Type};
-use std::collections::HashMap;
+use crate::builtins::Builtin;
+use crate::config::Config;
+use crate::diagnostic::Diagnostic;
+use crate::lexer::{NumberSuffix, raw_byte};
+use crate::parser::{AstNode, Expr, Op, Param, Position, Stmts, Type, UnaryOp};
+use std::collections::{HashMap, HashSet};
 
 struct SubProgCtx {
+    //The arity is param_types.len(), there is no separate count that could overflow
     param_types: Vec<Type>,
     return_type: Type,
+    noreturn: bool,
 }
 
 #[derive(Debug)]
@@ -13,39 +19,106 @@ struct VarCtx {
     decl_level: usize,
 }
 
-struct SemError {
-    msg: String,
-    position: Position,
+//Generated C code names its temporaries with this prefix so user names must avoid it
+fn is_reserved_name(name: &str) -> bool {
+    name.starts_with("__")
 }
 
-impl std::fmt::Display for SemError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        write!(
-            f,
-            "{}:{}:{}: \x1b[31merror:\x1b[0m {}",
-            self.position.filename, self.position.row, self.position.column, self.msg
-        )
-    }
+//Names pass through to the generated C unchanged, so they can not be one of its keywords.
+//bool, true and false come from stdbool.h
+fn is_c_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "auto"
+            | "bool"
+            | "break"
+            | "case"
+            | "char"
+            | "const"
+            | "continue"
+            | "default"
+            | "do"
+            | "double"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "float"
+            | "for"
+            | "goto"
+            | "if"
+            | "inline"
+            | "int"
+            | "long"
+            | "register"
+            | "restrict"
+            | "return"
+            | "short"
+            | "signed"
+            | "sizeof"
+            | "static"
+            | "struct"
+            | "switch"
+            | "true"
+            | "typedef"
+            | "union"
+            | "unsigned"
+            | "void"
+            | "volatile"
+            | "while"
+    )
 }
 
 pub struct SemanticAnalyzer {
     is_subprogram: bool,
+    //args and arg read argc and argv which only the entry point has
+    in_main: bool,
+    //A return is an error in a subprogram marked noreturn
+    in_noreturn: bool,
+    //Name of the subprogram that becomes the C main
+    entry: String,
     expected_return_type: Type,
     subprogram_table: HashMap<String, SubProgCtx>,
     local_var_table: HashMap<String, VarCtx>,
-    errors: Vec<SemError>,
+    errors: Vec<Diagnostic>,
+    warnings: Vec<Diagnostic>,
+    //Every subprogram that is called somewhere, builtins excluded
+    called: HashSet<String>,
+    unused_warnings: bool,
+    empty_body_warnings: bool,
+    //Warnings are reported as errors instead
+    werror: bool,
     decl_level: usize,
+    //Set while analyzing an operand that and, or or ?: may skip, a binding in there could be
+    //left unassigned when the body uses it
+    maybe_skipped: bool,
+    //The labels of the loops the current statement is nested in, innermost last. break and
+    //continue need at least one and a label has to be in here
+    loop_labels: Vec<Option<String>>,
+    //The type each set, write and return ended up with, in the order they were analyzed
+    inferred_types: Vec<(Position, Type)>,
 }
 
 impl SemanticAnalyzer {
-    pub fn new() -> SemanticAnalyzer {
+    pub fn new(config: &Config) -> SemanticAnalyzer {
         SemanticAnalyzer {
             is_subprogram: false,
+            in_main: false,
+            in_noreturn: false,
+            entry: config.entry.clone(),
             expected_return_type: Type::Unknown,
             subprogram_table: HashMap::new(),
             local_var_table: HashMap::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            called: HashSet::new(),
+            unused_warnings: config.unused_warnings,
+            empty_body_warnings: config.empty_body_warnings,
+            werror: config.werror,
             decl_level: 0,
+            maybe_skipped: false,
+            loop_labels: Vec::new(),
+            inferred_types: Vec::new(),
         }
     }
 
@@ -61,43 +134,101 @@ impl SemanticAnalyzer {
         self.decl_level -= 1;
     }
 
-    pub fn analyze_ast(self: &mut Self, ast: &mut [AstNode<Stmts>]) {
+    //Checked in the first pass along with the rest of the signature
+    fn check_params(self: &mut Self, params: &[Param]) {
+        let mut param_names = HashSet::new();
+        for param in params {
+            if !param_names.insert(param.name.as_str()) {
+                self.errors.push(Diagnostic::error(
+                    format!("parameter {} is declared more than once", param.name),
+                    param.position.clone(),
+                ));
+            }
+            if is_reserved_name(&param.name) {
+                self.errors.push(Diagnostic::error(
+                    format!(
+                        "parameter {} uses the __ prefix reserved for the compiler",
+                        param.name
+                    ),
+                    param.position.clone(),
+                ));
+            }
+            if is_c_keyword(&param.name) {
+                self.errors.push(Diagnostic::error(
+                    format!("parameter {} is a keyword in C", param.name),
+                    param.position.clone(),
+                ));
+            }
+        }
+    }
+
+    //Calls to exit and to noreturn subprograms never come back
+    fn is_noreturn_call(self: &Self, name: &str) -> bool {
+        match Builtin::from_name(name) {
+            Some(builtin) => builtin == Builtin::Exit,
+            None => self
+                .subprogram_table
+                .get(name)
+                .is_some_and(|subprogram| subprogram.noreturn),
+        }
+    }
+
+    //Every error found is handed back so the caller decides how to report them and whether to exit
+    pub fn analyze_ast(self: &mut Self, ast: &mut [AstNode<Stmts>]) -> Result<(), Vec<Diagnostic>> {
         for node in ast.iter_mut() {
             match &node.value {
                 Stmts::SubProgramDef {
                     name,
                     return_type,
                     params,
+                    noreturn,
                     ..
                 } => {
-                    if self.subprogram_table.contains_key(name) {
-                        self.errors.push(SemError {
-                            msg: format!("redefinition of function {name}"),
-                            position: node.position.clone(),
-                        });
+                    self.check_params(params);
+                    if Builtin::from_name(name).is_some() {
+                        //Calls would always go to the builtin
+                        self.errors.push(Diagnostic::error(
+                            format!("subprogram {name} has the same name as a builtin"),
+                            node.position.clone(),
+                        ));
+                    } else if is_c_keyword(name) {
+                        self.errors.push(Diagnostic::error(
+                            format!("subprogram {name} is a keyword in C"),
+                            node.position.clone(),
+                        ));
+                    } else if self.subprogram_table.contains_key(name) {
+                        self.errors.push(Diagnostic::error(
+                            format!("redefinition of function {name}"),
+                            node.position.clone(),
+                        ));
                     } else {
-                        let is_main = name == "main";
-                        if is_main {
+                        let entry = &self.entry;
+                        if name == entry {
                             if *return_type == Type::Void {
-                                self.errors.push(SemError {
-                                    msg: "main should be a function not a procedure".to_string(),
-                                    position: node.position.clone(),
-                                });
+                                self.errors.push(Diagnostic::error(
+                                    format!("{entry} should be a function not a procedure"),
+                                    node.position.clone(),
+                                ));
                             } else if *return_type != Type::Int {
-                                self.errors.push(SemError {
-                                    msg: "main function must have return type Int".to_string(),
-                                    position: node.position.clone(),
-                                });
+                                self.errors.push(Diagnostic::error(
+                                    format!("{entry} function must have return type Int"),
+                                    node.position.clone(),
+                                ));
                             }
-                            if params.len() != 0 {
-                                self.errors.push(SemError {
-                                    msg: "main function does not take any arguement".to_string(),
-                                    position: node.position.clone(),
-                                });
+                            if !params.is_empty() {
+                                self.errors.push(Diagnostic::error(
+                                    format!("{entry} function does not take any arguement"),
+                                    node.position.clone(),
+                                ));
                             }
+                        } else if name == "main" {
+                            //The entry point takes the C name main
+                            self.errors.push(Diagnostic::error(format!(
+                                    "subprogram main can not be defined when the entry point is {entry}"
+                                ), node.position.clone()));
                         }
                         let param_types = params
-                            .into_iter()
+                            .iter()
                             .map(|param| param.param_type)
                             .collect::<Vec<Type>>();
                         self.subprogram_table.insert(
@@ -105,6 +236,7 @@ impl SemanticAnalyzer {
                             SubProgCtx {
                                 param_types,
                                 return_type: *return_type,
+                                noreturn: *noreturn,
                             },
                         );
                     }
@@ -112,84 +244,414 @@ impl SemanticAnalyzer {
                 _ => continue,
             }
         }
-        if !self.subprogram_table.contains_key("main") {
-            eprintln!("\x1b[31merror:\x1b[0m main function not found");
-            std::process::exit(1);
+        if !self.subprogram_table.contains_key(&self.entry) {
+            //There is no node to point at so the error goes at the start of the file
+            let position = ast
+                .first()
+                .map(|node| Position {
+                    filename: node.position.filename.clone(),
+                    column: 1,
+                    raw_column: 1,
+                    row: 1,
+                })
+                .unwrap_or_default();
+            return Err(vec![Diagnostic::error(
+                format!("{} function not found", self.entry),
+                position,
+            )]);
         }
 
-        for node in ast {
+        for node in ast.iter_mut() {
             self.analyze_stmt(node);
         }
+        //Warnings come before the error check so that --werror can fail the program
+        if self.unused_warnings {
+            self.warn_uncalled_subprograms(ast);
+        }
+        //Some diagnostics are only found after the whole program was walked, so they are put
+        //back in source order. The sort is stable so diagnostics at the same spot keep theirs
+        self.errors
+            .sort_by_key(|error| (error.position.row, error.position.column));
+        self.warnings
+            .sort_by_key(|warning| (warning.position.row, warning.position.column));
 
         if !self.errors.is_empty() {
-            self.errors.iter_mut().for_each(|err| {
-                eprintln!("{err}");
-            });
-            std::process::exit(1);
+            return Err(std::mem::take(&mut self.errors));
+        }
+        Ok(())
+    }
+
+    //Warnings are kept even when analysis fails, so they are taken separately from the errors
+    pub fn take_warnings(self: &mut Self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    //For --explain-types, sorted by position like the diagnostics
+    pub fn take_inferred_types(self: &mut Self) -> Vec<(Position, Type)> {
+        let mut inferred_types = std::mem::take(&mut self.inferred_types);
+        inferred_types.sort_by_key(|(position, _)| (position.row, position.column));
+        inferred_types
+    }
+
+    fn warn(self: &mut Self, code: &'static str, msg: String, position: Position) {
+        if self.werror {
+            self.errors
+                .push(Diagnostic::error(format!("{msg} [--werror]"), position).with_code(code));
+        } else {
+            self.warnings
+                .push(Diagnostic::warning(msg, position).with_code(code));
+        }
+    }
+
+    //`if c then end` compiles to an if that does nothing, which is almost always a mistake
+    fn warn_empty_body(self: &mut Self, kind: &str, stmts: &[AstNode<Stmts>], position: &Position) {
+        if self.empty_body_warnings && stmts.is_empty() {
+            self.warn(
+                "empty-body",
+                format!("empty '{kind}' body"),
+                position.clone(),
+            );
+        }
+    }
+
+    //Shared by set and by bindings in conditions
+    fn check_new_variable(self: &mut Self, name: &str, position: &Position) {
+        if name != "_"
+            && let Some(var_ctx) = self.local_var_table.get(name)
+            && var_ctx.decl_level == self.decl_level
+        {
+            self.errors.push(Diagnostic::error(
+                format!("redefinition of variable {name}",),
+                position.clone(),
+            ));
+        }
+        if is_reserved_name(name) {
+            self.errors.push(Diagnostic::error(
+                format!("variable {name} uses the __ prefix reserved for the compiler"),
+                position.clone(),
+            ));
+        }
+        if is_c_keyword(name) {
+            self.errors.push(Diagnostic::error(
+                format!("variable {name} is a keyword in C"),
+                position.clone(),
+            ));
+        }
+        //In C the variable would hide the function for the rest of the block
+        if self.subprogram_table.contains_key(name) {
+            self.errors.push(Diagnostic::error(
+                format!("variable {name} conflicts with a subprogram of the same name"),
+                position.clone(),
+            ));
+        }
+    }
+
+    fn analyze_loop_body(
+        self: &mut Self,
+        label: &Option<String>,
+        stmts: &mut [AstNode<Stmts>],
+        position: &Position,
+    ) {
+        if let Some(label) = label
+            && self
+                .loop_labels
+                .iter()
+                .flatten()
+                .any(|outer| outer == label)
+        {
+            self.errors.push(Diagnostic::error(
+                format!("loop label {label} is already used by an enclosing loop"),
+                position.clone(),
+            ));
+        }
+        self.loop_labels.push(label.clone());
+        for stmt in stmts.iter_mut() {
+            self.analyze_stmt(stmt)
+        }
+        self.loop_labels.pop();
+    }
+
+    fn check_loop_jump(
+        self: &mut Self,
+        keyword: &str,
+        label: &Option<String>,
+        position: &Position,
+    ) {
+        if self.loop_labels.is_empty() {
+            self.errors.push(Diagnostic::error(
+                format!("{keyword} outside of a loop"),
+                position.clone(),
+            ));
+        } else if let Some(label) = label
+            && !self
+                .loop_labels
+                .iter()
+                .flatten()
+                .any(|outer| outer == label)
+        {
+            self.errors.push(Diagnostic::error(
+                format!("{keyword} to unknown loop label {label}"),
+                position.clone(),
+            ));
+        }
+    }
+
+    //Only possible once every body has been walked, a call can come after the definition
+    fn warn_uncalled_subprograms(self: &mut Self, ast: &[AstNode<Stmts>]) {
+        for node in ast {
+            if let Stmts::SubProgramDef { name, .. } = &node.value
+                && *name != self.entry
+                && Builtin::from_name(name).is_none()
+                && !self.called.contains(name)
+            {
+                self.warn(
+                    "unused-subprogram",
+                    format!("subprogram {name} is never called"),
+                    node.position.clone(),
+                );
+            }
+        }
+    }
+
+    //min, max and abs return whatever numeric type they are given. The first argument is typed
+    //by the surrounding expression and the others have to match it
+    fn analyze_numeric_args(
+        self: &mut Self,
+        builtin: Builtin,
+        name: &str,
+        args: &mut [AstNode<Expr>],
+        expected_type: Type,
+    ) -> Type {
+        let mut numeric_type = Type::Unknown;
+        for arg in args.iter_mut() {
+            let arg_expected = if numeric_type == Type::Unknown {
+                expected_type
+            } else {
+                numeric_type
+            };
+            let arg_type = self.analyze_expr(arg, arg_expected);
+            if arg_type == Type::Unknown {
+                continue;
+            }
+            if !builtin.accepts_numeric(arg_type) {
+                let expected = if builtin == Builtin::Abs {
+                    "an int or float argument"
+                } else {
+                    "int, nat or float arguments"
+                };
+                self.errors.push(Diagnostic::error(
+                    format!("{name} expects {expected}, found {arg_type:?}"),
+                    arg.position.clone(),
+                ));
+            } else if numeric_type == Type::Unknown {
+                numeric_type = arg_type;
+            } else if arg_type != numeric_type {
+                self.errors.push(Diagnostic::error(format!(
+                        "{name} expects arguments of the same type, found {numeric_type:?} and {arg_type:?}"
+                    ), arg.position.clone()));
+            }
+        }
+        numeric_type
+    }
+
+    fn check_to_string_arg(self: &mut Self, arg_type: Type, position: &Position) {
+        match arg_type {
+            Type::Int | Type::Nat | Type::Float | Type::Bool | Type::Char | Type::Unknown => {}
+            Type::String => self.warn(
+                "useless-to-string",
+                "to_string of a string does nothing".to_string(),
+                position.clone(),
+            ),
+            Type::Void => self.errors.push(Diagnostic::error(
+                "to_string expects an int, nat, float, bool or char argument, found Void"
+                    .to_string(),
+                position.clone(),
+            )),
+        }
+    }
+
+    //Returns the return type of the called subprogram or None if it does not exist
+    fn analyze_call(
+        self: &mut Self,
+        name: &str,
+        args: &mut [AstNode<Expr>],
+        expected_type: Type,
+        arg_types: &mut Vec<Type>,
+        position: &Position,
+    ) -> Option<Type> {
+        let builtin = Builtin::from_name(name);
+        let (param_types, return_type) = if let Some(builtin) = builtin {
+            (builtin.param_types(), builtin.return_type())
+        } else if let Some(subprogram) = self.subprogram_table.get(name) {
+            self.called.insert(name.to_string());
+            (subprogram.param_types.clone(), subprogram.return_type)
+        } else {
+            self.errors.push(Diagnostic::error(
+                format!("subprogram {name} is not defined"),
+                position.clone(),
+            ));
+            return None;
+        };
+        //The entry becomes the C main which takes argc and argv instead of its parameters
+        if builtin.is_none() && name == self.entry {
+            self.errors.push(Diagnostic::error(
+                format!("the entry point {name} can not be called"),
+                position.clone(),
+            ));
+        }
+        if builtin.is_some_and(|builtin| builtin.is_main_only()) && !self.in_main {
+            self.errors.push(Diagnostic::error(
+                format!("{name} can only be used inside {}", self.entry),
+                position.clone(),
+            ));
+        }
+        if args.len() != param_types.len() {
+            self.errors.push(Diagnostic::error(
+                format!(
+                    "subprogram {name} takes {} arguements but {} were given",
+                    param_types.len(),
+                    args.len()
+                ),
+                position.clone(),
+            ));
+        }
+        if let Some(builtin) = builtin
+            && builtin.is_numeric()
+        {
+            let numeric_type = self.analyze_numeric_args(builtin, name, args, expected_type);
+            *arg_types = vec![numeric_type; args.len()];
+            return Some(numeric_type);
+        }
+        for (arg, param_type) in args.iter_mut().zip(param_types) {
+            let arg_type = self.analyze_expr(arg, param_type);
+            arg_types.push(arg_type);
+            if builtin == Some(Builtin::ToString) {
+                self.check_to_string_arg(arg_type, &arg.position);
+            } else if builtin.is_some_and(|builtin| builtin.takes_integer())
+                && !matches!(arg_type, Type::Int | Type::Nat | Type::Unknown)
+            {
+                self.errors.push(Diagnostic::error(
+                    format!("{name} expects an int or nat argument, found {arg_type:?}"),
+                    arg.position.clone(),
+                ));
+            }
+        }
+        Some(return_type)
+    }
+
+    //TODO: change it to use literals
+    fn analyze_number(
+        self: &mut Self,
+        num: i128,
+        suffix: Option<NumberSuffix>,
+        expected_type: Type,
+        position: &Position,
+    ) -> Type {
+        //A suffix wins over the type the context expects
+        let literal_type = match suffix {
+            Some(NumberSuffix::Nat) => Type::Nat,
+            Some(NumberSuffix::Int) => Type::Int,
+            None => expected_type,
+        };
+        if suffix.is_some() && expected_type != Type::Unknown && literal_type != expected_type {
+            self.errors.push(Diagnostic::error(
+                format!(
+                    "Expected type {:?}, found {:?} literal",
+                    expected_type, literal_type
+                ),
+                position.clone(),
+            ));
+            return expected_type;
+        }
+        //Nothing gives the literal a type, as in write(5), so it is an Int unless it only
+        //fits a Nat: 2147483647 is an Int and 2147483648 is a Nat
+        if literal_type == Type::Unknown {
+            if num >= i32::MIN as i128 && num <= i32::MAX as i128 {
+                Type::Int
+            } else if num >= u32::MIN as i128 && num <= u32::MAX as i128 {
+                Type::Nat
+            } else {
+                self.errors.push(Diagnostic::error(
+                    "The number passed is too large to be represented by any integer type"
+                        .to_string(),
+                    position.clone(),
+                ));
+                Type::Unknown
+            }
+        } else if literal_type == Type::Int {
+            if num < i32::MIN as i128 {
+                self.errors.push(Diagnostic::error(
+                    "The number passed is too small to be represented by type int".to_string(),
+                    position.clone(),
+                ));
+                literal_type
+            } else if num > i32::MAX as i128 {
+                self.errors.push(Diagnostic::error(
+                    "The number passed is too large to be represented by type int".to_string(),
+                    position.clone(),
+                ));
+                literal_type
+            } else {
+                literal_type
+            }
+        } else if literal_type == Type::Nat {
+            if num < u32::MIN as i128 {
+                self.errors.push(Diagnostic::error(
+                    "The number passed is too small to be represented by type nat".to_string(),
+                    position.clone(),
+                ));
+                literal_type
+            } else if num > u32::MAX as i128 {
+                self.errors.push(Diagnostic::error(
+                    "The number passed is too large to be represented by type nat".to_string(),
+                    position.clone(),
+                ));
+                literal_type
+            } else {
+                literal_type
+            }
+        } else {
+            self.errors.push(Diagnostic::error(
+                format!("Expected type {:?}, found number", literal_type),
+                position.clone(),
+            ));
+            literal_type
         }
     }
 
     //TODO: investigate whether we should return early when we detect errors
-    fn analyze_expr(self: &mut Self, expr: &AstNode<Expr>, expected_type: Type) -> Type {
-        match &expr.value {
-            //TODO: Abstract this into it's own function and change it to use literals
-            Expr::Number(num) => {
-                if expected_type == Type::Unknown {
-                    if *num >= i32::MIN as i128 && *num <= i32::MAX as i128 {
-                        Type::Int
-                    } else if *num >= u32::MIN as i128 && *num <= u32::MAX as i128 {
-                        Type::Nat
-                    } else {
-                        todo!()
-                    }
-                } else if expected_type == Type::Int {
-                    if *num < i32::MIN as i128 {
-                        self.errors.push(SemError {
-                            msg: "The number passed is too small to be represented by type int"
-                                .to_string(),
-                            position: expr.position.clone(),
-                        });
-                        expected_type
-                    } else if *num > i32::MAX as i128 {
-                        self.errors.push(SemError {
-                            msg: "The number passed is too large to be represented by type int"
-                                .to_string(),
-                            position: expr.position.clone(),
-                        });
-                        expected_type
-                    } else {
-                        expected_type
-                    }
-                } else if expected_type == Type::Nat {
-                    if *num < u32::MIN as i128 {
-                        self.errors.push(SemError {
-                            msg: "The number passed is too small to be represented by type nat"
-                                .to_string(),
-                            position: expr.position.clone(),
-                        });
-                        expected_type
-                    } else if *num > u32::MAX as i128 {
-                        self.errors.push(SemError {
-                            msg: "The number passed is too large to be represented by type nat"
-                                .to_string(),
-                            position: expr.position.clone(),
-                        });
-                        expected_type
-                    } else {
-                        expected_type
-                    }
+    fn analyze_expr(self: &mut Self, expr: &mut AstNode<Expr>, expected_type: Type) -> Type {
+        match &mut expr.value {
+            //The C literal is written for the type the number ends up with
+            Expr::Number(num, suffix, num_type) => {
+                *num_type = self.analyze_number(*num, *suffix, expected_type, &expr.position);
+                *num_type
+            }
+            Expr::Float(num) => {
+                if !num.is_finite() {
+                    self.errors.push(Diagnostic::error(
+                        "The float passed is too large to be represented by type float".to_string(),
+                        expr.position.clone(),
+                    ));
+                }
+                if expected_type == Type::Float || expected_type == Type::Unknown {
+                    Type::Float
                 } else {
-                    todo!()
+                    self.errors.push(Diagnostic::error(
+                        format!("Expected type {:?}, found float", expected_type),
+                        expr.position.clone(),
+                    ));
+                    expected_type
                 }
             }
             Expr::Bool(_) => {
                 if expected_type == Type::Bool || expected_type == Type::Unknown {
                     Type::Bool
                 } else {
-                    self.errors.push(SemError {
-                        msg: format!("Expected type {:?}, found boolean", expected_type),
-                        position: expr.position.clone(),
-                    });
+                    self.errors.push(Diagnostic::error(
+                        format!("Expected type {:?}, found boolean", expected_type),
+                        expr.position.clone(),
+                    ));
                     expected_type
                 }
             }
@@ -197,79 +659,334 @@ impl SemanticAnalyzer {
                 if expected_type == Type::String || expected_type == Type::Unknown {
                     Type::String
                 } else {
-                    self.errors.push(SemError {
-                        msg: format!("Expected type {:?}, found string literal", expected_type),
-                        position: expr.position.clone(),
-                    });
+                    self.errors.push(Diagnostic::error(
+                        format!("Expected type {:?}, found string literal", expected_type),
+                        expr.position.clone(),
+                    ));
+                    expected_type
+                }
+            }
+            Expr::Char(c) => {
+                //A C char is a single byte, which a \x80 to \xff escape also is
+                if !c.is_ascii() && raw_byte(*c).is_none() {
+                    self.errors.push(Diagnostic::error(
+                        format!("char literal {c:?} is not ascii"),
+                        expr.position.clone(),
+                    ));
+                }
+                if expected_type == Type::Char || expected_type == Type::Unknown {
+                    Type::Char
+                } else {
+                    self.errors.push(Diagnostic::error(
+                        format!("Expected type {:?}, found char literal", expected_type),
+                        expr.position.clone(),
+                    ));
                     expected_type
                 }
             }
             //TODO: get name of variable and check if the type matches
             Expr::Variable(name) => {
                 if !self.local_var_table.contains_key(name) {
-                    self.errors.push(SemError {
-                        msg: format!("use of unknown variable {name}"),
-                        position: expr.position.clone(),
-                    });
+                    self.errors.push(Diagnostic::error(
+                        format!("use of unknown variable {name}"),
+                        expr.position.clone(),
+                    ));
                     return expected_type;
                 }
                 self.local_var_table.get(name).unwrap().var_type
             }
-            Expr::SubprogramCall { name, args } => {
-                if !self.subprogram_table.contains_key(name) {
-                    self.errors.push(SemError {
-                        msg: format!("subprogram {name} is not defined"),
-                        position: expr.position.clone(),
-                    });
-                    return expected_type;
+            Expr::SubprogramCall {
+                name,
+                args,
+                arg_types,
+            } => {
+                let is_statement_only =
+                    Builtin::from_name(name).is_some_and(|builtin| builtin.is_statement_only());
+                if is_statement_only {
+                    self.errors.push(Diagnostic::error(
+                        format!("{name} can only be called as a statement"),
+                        expr.position.clone(),
+                    ));
+                }
+                match self.analyze_call(name, args, expected_type, arg_types, &expr.position) {
+                    Some(Type::Void) if !is_statement_only => {
+                        self.errors.push(Diagnostic::error(
+                            format!(
+                                "cannot use the result of procedure {name} (returns void) in an expression"
+                            ),
+                            expr.position.clone(),
+                        ));
+                        expected_type
+                    }
+                    Some(return_type) => return_type,
+                    None => expected_type,
+                }
+            }
+            //not and ! only negate a bool, whatever the context expects
+            Expr::Unary {
+                op: UnaryOp::Not,
+                expr: operand,
+            } => {
+                let operand_type = self.analyze_expr(operand, Type::Unknown);
+                if !matches!(operand_type, Type::Bool | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("operator not expects a Bool operand, found {operand_type:?}"),
+                        expr.position.clone(),
+                    ));
+                } else if !matches!(expected_type, Type::Bool | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("Expected type {expected_type:?}, found boolean"),
+                        expr.position.clone(),
+                    ));
+                }
+                Type::Bool
+            }
+            Expr::Conditional {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                let cond_type = self.analyze_expr(cond, Type::Unknown);
+                if !matches!(cond_type, Type::Bool | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("condition of ?: must be of type Bool, found {cond_type:?}"),
+                        cond.position.clone(),
+                    ));
+                }
+                let outer = std::mem::replace(&mut self.maybe_skipped, true);
+                let then_type = self.analyze_expr(then_expr, expected_type);
+                let else_type = self.analyze_expr(else_expr, expected_type);
+                self.maybe_skipped = outer;
+                if then_type != else_type
+                    && then_type != Type::Unknown
+                    && else_type != Type::Unknown
+                {
+                    self.errors.push(Diagnostic::error(format!(
+                            "type mismatch in conditional expression then:{then_type:?} while else:{else_type:?}"
+                        ), expr.position.clone()));
+                }
+                then_type
+            }
+            Expr::Index {
+                expr: indexed,
+                index,
+            } => {
+                let indexed_type = self.analyze_expr(indexed, Type::Unknown);
+                if !matches!(indexed_type, Type::String | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(format!("cannot index a value of type {indexed_type:?}, only strings can be indexed"), expr.position.clone()));
+                }
+                let index_type = self.analyze_expr(index, Type::Nat);
+                if !matches!(index_type, Type::Nat | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("string index must be of type Nat, found {index_type:?}"),
+                        index.position.clone(),
+                    ));
+                }
+                Type::Char
+            }
+            //If and while open the block of their body before their condition is analyzed, so
+            //the variable goes away with the body
+            Expr::Bind {
+                name,
+                expr: value,
+                var_type,
+            } => {
+                self.check_new_variable(name, &expr.position);
+                if self.maybe_skipped {
+                    self.errors.push(Diagnostic::error(
+                        format!(
+                            "binding {name} may be skipped by and, or or ?:, it can only be used where it is always evaluated"
+                        ),
+                        expr.position.clone(),
+                    ));
                 }
-                //TODO: check arity
-                let arg_types = self.subprogram_table.get(name).unwrap().param_types.clone();
-                for (i, arg) in args.iter().enumerate() {
-                    let _ = self.analyze_expr(arg, arg_types[i]);
+                *var_type = self.analyze_expr(value, Type::Unknown);
+                self.local_var_table.insert(
+                    name.clone(),
+                    VarCtx {
+                        var_type: *var_type,
+                        mutable: false,
+                        decl_level: self.decl_level,
+                    },
+                );
+                *var_type
+            }
+            Expr::Unary {
+                op: UnaryOp::Neg,
+                expr: operand,
+            } => {
+                let gotten_type = self.analyze_expr(operand, expected_type);
+                if !matches!(gotten_type, Type::Int | Type::Float | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("cannot negate a value of type {gotten_type:?}"),
+                        expr.position.clone(),
+                    ));
                 }
-                self.subprogram_table.get(name).unwrap().return_type
+                gotten_type
             }
             //TODO: analyze based on the operation kind
-            Expr::Binary { lhs, rhs, .. } => {
-                let mut lhs_type = expected_type;
-                let mut rhs_type = expected_type;
-                lhs_type = self.analyze_expr(lhs, lhs_type);
-
-                rhs_type = self.analyze_expr(rhs, rhs_type);
-                //TODO: make this error better
-                if rhs_type != lhs_type {
-                    self.errors.push(SemError {
-                        msg: format!(
+            Expr::Binary {
+                op,
+                lhs,
+                rhs,
+                operand_type,
+            } => {
+                //1 < 2 < 3 would compare the bool 1 < 2 with 3, which is never what was meant
+                if op.is_comparison()
+                    && let Expr::Binary {
+                        op: inner_op,
+                        lhs: first,
+                        rhs: middle,
+                        ..
+                    } = &lhs.value
+                    && inner_op.is_comparison()
+                {
+                    let suggestion = format!(
+                        "{} {inner_op} {} and {} {op} {}",
+                        first.value, middle.value, middle.value, rhs.value
+                    );
+                    self.errors.push(Diagnostic::error(
+                        format!(
+                            "comparison operators cannot be chained; did you mean '{suggestion}'?"
+                        ),
+                        expr.position.clone(),
+                    ));
+                    self.analyze_expr(lhs, Type::Unknown);
+                    self.analyze_expr(rhs, Type::Unknown);
+                    return Type::Bool;
+                }
+                //Comparisons yield a bool whatever they compare, so their operands are only
+                //typed by each other and not by what the surrounding expression expects
+                let lhs_expected = if op.is_comparison() {
+                    Type::Unknown
+                } else if op.is_logical() {
+                    Type::Bool
+                } else {
+                    expected_type
+                };
+                //An untyped literal on the left takes its type from the right like it would the
+                //other way around, so `0 < n` is a Nat comparison when n is a Nat
+                let (lhs_type, rhs_type) =
+                    if op.is_comparison() && matches!(lhs.value, Expr::Number(_, None, _)) {
+                        let rhs_type = self.analyze_expr(rhs, Type::Unknown);
+                        (self.analyze_expr(lhs, rhs_type), rhs_type)
+                    } else {
+                        let lhs_type = self.analyze_expr(lhs, lhs_expected);
+                        let rhs_expected = if op.is_comparison() {
+                            lhs_type
+                        } else if matches!(op, Op::Add) && lhs_type == Type::String {
+                            //Typed on its own so a non string gets the concatenation error below
+                            Type::Unknown
+                        } else {
+                            lhs_expected
+                        };
+                        let outer = self.maybe_skipped;
+                        self.maybe_skipped |= op.is_logical();
+                        let rhs_type = self.analyze_expr(rhs, rhs_expected);
+                        self.maybe_skipped = outer;
+                        (lhs_type, rhs_type)
+                    };
+                let is_concat = matches!(op, Op::Add) && lhs_type == Type::String;
+                let is_concat = is_concat || matches!(op, Op::Add) && rhs_type == Type::String;
+                //C converts the int32_t to uint32_t for the comparison, so a negative Int
+                //compares as a huge Nat
+                let is_mixed_sign = matches!(
+                    (lhs_type, rhs_type),
+                    (Type::Int, Type::Nat) | (Type::Nat, Type::Int)
+                );
+                if op.is_comparison() && is_mixed_sign {
+                    self.warn(
+                        "sign-compare",
+                        format!(
+                            "comparison between {lhs_type:?} and {rhs_type:?} may behave unexpectedly, a negative Int is compared as a large Nat"
+                        ),
+                        expr.position.clone(),
+                    );
+                } else if is_concat && rhs_type != lhs_type {
+                    let other_type = if lhs_type == Type::String {
+                        rhs_type
+                    } else {
+                        lhs_type
+                    };
+                    self.errors.push(Diagnostic::error(format!(
+                            "cannot concatenate String and {other_type:?}, convert the {other_type:?} with to_string first"
+                        ), expr.position.clone()));
+                } else if rhs_type != lhs_type {
+                    //TODO: make this error better
+                    self.errors.push(Diagnostic::error(format!(
                             "type mismatch in binary expression lhs:{lhs_type:?} while rhs:{rhs_type:?}"
+                        ), expr.position.clone()));
+                } else if lhs_type == Type::String && !is_concat {
+                    //Strings are structs in C so + is the only operator that works on them
+                    self.errors.push(Diagnostic::error(
+                        format!("operator {op} is not defined for String"),
+                        expr.position.clone(),
+                    ));
+                } else if lhs_type == Type::Char && !op.is_comparison() && !op.is_logical() {
+                    //'a' + 1 would need a rule for what comes after 'z', so chars can only be
+                    //compared. Use to_string to build strings out of them
+                    self.errors.push(Diagnostic::error(
+                        format!(
+                            "operator {op} is not defined for Char, chars can only be compared"
                         ),
-                        position: expr.position.clone(),
-                    });
+                        expr.position.clone(),
+                    ));
+                } else if matches!(op, Op::Mod) && lhs_type == Type::Float {
+                    self.errors.push(Diagnostic::error(
+                        format!("operator {op} is not defined for Float"),
+                        expr.position.clone(),
+                    ));
+                } else if matches!(op, Op::Pow) && lhs_type == Type::Bool {
+                    self.errors.push(Diagnostic::error(
+                        format!("operator {op} expects numeric operands, found {lhs_type:?}"),
+                        expr.position.clone(),
+                    ));
+                } else if op.is_logical() && !matches!(lhs_type, Type::Bool | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("operator {op} expects bool operands, found {lhs_type:?}"),
+                        expr.position.clone(),
+                    ));
+                }
+                *operand_type = lhs_type;
+                if op.is_comparison() || op.is_logical() {
+                    Type::Bool
+                } else {
+                    lhs_type
                 }
-                lhs_type
             }
         }
     }
 
     fn analyze_stmt(self: &mut Self, node: &mut AstNode<Stmts>) {
         match &mut node.value {
-            Stmts::Write { type_, expr } => {
-                let gotten_type = self.analyze_expr(&expr, Type::Unknown);
+            Stmts::Write { type_, expr, .. } => {
+                //A procedure call is already an error in an expression, so this is never Void
+                let gotten_type = self.analyze_expr(expr, Type::Unknown);
                 *type_ = gotten_type;
+                self.inferred_types
+                    .push((node.position.clone(), gotten_type));
             }
             Stmts::Return { return_type, expr } => {
+                if self.in_noreturn {
+                    self.errors.push(Diagnostic::error(
+                        "a noreturn subprogram can not return".to_string(),
+                        node.position.clone(),
+                    ));
+                }
                 //TODO: Check if it matches function return type
-                let gotten_type = self.analyze_expr(&expr, Type::Unknown);
+                let gotten_type = self.analyze_expr(expr, Type::Unknown);
                 if gotten_type != self.expected_return_type {
-                    self.errors.push(SemError {
-                        msg: format!(
+                    self.errors.push(Diagnostic::error(
+                        format!(
                             "Expected return type {:?}, found {:?}",
                             self.expected_return_type, gotten_type
                         ),
-                        position: expr.position.clone(),
-                    });
+                        expr.position.clone(),
+                    ));
                 }
                 *return_type = gotten_type;
+                self.inferred_types
+                    .push((node.position.clone(), gotten_type));
             }
             Stmts::Set {
                 name,
@@ -277,32 +994,30 @@ impl SemanticAnalyzer {
                 expr,
                 mutable,
             } => {
-                if self.local_var_table.contains_key(name) {
-                    let var_ctx = self.local_var_table.get(name).unwrap();
-                    if var_ctx.decl_level == self.decl_level {
-                        self.errors.push(SemError {
-                            msg: format!("redefinition of variable {name}",),
-                            position: node.position.clone(),
-                        });
-                    }
-                }
-                let gotten_type = self.analyze_expr(expr, var_type.clone());
+                self.check_new_variable(name, &node.position);
+                let gotten_type = self.analyze_expr(expr, *var_type);
                 *var_type = gotten_type;
+                self.inferred_types
+                    .push((node.position.clone(), gotten_type));
+                //"_" only evaluates the expression so it never becomes a variable
+                if name == "_" {
+                    return;
+                }
                 self.local_var_table.insert(
                     name.clone(),
                     VarCtx {
-                        var_type: var_type.clone(),
-                        mutable: mutable.clone(),
+                        var_type: *var_type,
+                        mutable: *mutable,
                         decl_level: self.decl_level,
                     },
                 );
             }
             Stmts::Assign { name, expr } => {
                 if !self.local_var_table.contains_key(name) {
-                    self.errors.push(SemError {
-                        msg: format!("trying to assing value to unexisting variable: {name}",),
-                        position: node.position.clone(),
-                    });
+                    self.errors.push(Diagnostic::error(
+                        format!("trying to assing value to unexisting variable: {name}",),
+                        node.position.clone(),
+                    ));
                 } else {
                     //TODO: check if we can reassign the variable type for example after type
                     //inference
@@ -310,10 +1025,10 @@ impl SemanticAnalyzer {
                     if var_ctx.mutable {
                         let _ = self.analyze_expr(expr, var_ctx.var_type);
                     } else {
-                        self.errors.push(SemError {
-                            msg: format!("trying to assign value to immutable variable: {name}",),
-                            position: node.position.clone(),
-                        });
+                        self.errors.push(Diagnostic::error(
+                            format!("trying to assign value to immutable variable: {name}",),
+                            node.position.clone(),
+                        ));
                     }
                 }
             }
@@ -323,29 +1038,51 @@ impl SemanticAnalyzer {
                 stmts,
                 params,
                 name,
+                noreturn,
+                ..
             } => {
                 if self.is_subprogram {
-                    self.errors.push(SemError {
-                        msg: format!("Cannot define subprogram {name} inside another subprogram"),
-                        position: node.position.clone(),
-                    });
+                    self.errors.push(Diagnostic::error(
+                        format!("Cannot define subprogram {name} inside another subprogram"),
+                        node.position.clone(),
+                    ));
                     return;
                 }
                 self.is_subprogram = true;
+                self.in_main = *name == self.entry;
+                self.in_noreturn = *noreturn;
+                if *noreturn && self.in_main {
+                    self.errors.push(Diagnostic::error(
+                        format!("the entry point {name} can not be noreturn"),
+                        node.position.clone(),
+                    ));
+                }
                 self.expected_return_type = *return_type;
+                //Parameters live in the body's block, C does not let the body redeclare them
+                self.begin_block();
                 for param in params {
+                    //Only known once every subprogram is registered, so not checked with the
+                    //rest of the parameter list in the first pass
+                    if self.subprogram_table.contains_key(&param.name) {
+                        self.errors.push(Diagnostic::error(
+                            format!(
+                                "parameter {} conflicts with a subprogram of the same name",
+                                param.name
+                            ),
+                            param.position.clone(),
+                        ));
+                    }
                     self.local_var_table.insert(
                         param.name.clone(),
                         VarCtx {
                             var_type: param.param_type,
-                            mutable: false,
-                            decl_level: 0, // will be destroyed when the function exits
+                            mutable: param.mutable,
+                            decl_level: self.decl_level,
                         },
                     );
                 }
                 let mut return_stmt_exists = false;
                 //TODO: stronger, better checks for if stmts and what not
-                self.begin_block();
                 for stmt in stmts.iter_mut() {
                     if matches!(
                         stmt.value,
@@ -359,36 +1096,60 @@ impl SemanticAnalyzer {
                     self.analyze_stmt(stmt)
                 }
                 self.end_block();
-                if !return_stmt_exists && self.expected_return_type != Type::Void {
-                    self.errors.push(SemError {
-                        msg: format!("subprogram {name} does not have a return statement"),
-                        position: node.position.clone(),
-                    });
+                self.in_noreturn = false;
+                //C leaves falling off the end of a _Noreturn function undefined
+                let ends_program = matches!(
+                    stmts.last(),
+                    Some(AstNode {
+                        value: Stmts::SubProgramCall { name, .. },
+                        ..
+                    }) if self.is_noreturn_call(name)
+                );
+                if *noreturn && !ends_program {
+                    self.errors.push(Diagnostic::error(
+                        format!(
+                            "noreturn subprogram {name} must end with exit or a call to another noreturn subprogram"
+                        ),
+                        node.position.clone(),
+                    ));
+                }
+                //The entry point is allowed to fall off the end, codegen returns 0 for it, and a
+                //noreturn subprogram never gets to the end
+                if !return_stmt_exists
+                    && self.expected_return_type != Type::Void
+                    && *name != self.entry
+                    && !*noreturn
+                {
+                    self.errors.push(Diagnostic::error(
+                        format!("subprogram {name} does not have a return statement"),
+                        node.position.clone(),
+                    ));
                     return;
                 }
                 self.is_subprogram = false;
+                self.in_main = false;
             }
             Stmts::SubProgramCall { name, args } => {
-                if !self.subprogram_table.contains_key(name) {
-                    self.errors.push(SemError {
-                        msg: format!("subprogram {name} is not defined"),
-                        position: node.position.clone(),
-                    });
+                let Some(return_type) =
+                    self.analyze_call(name, args, Type::Unknown, &mut Vec::new(), &node.position)
+                else {
                     return;
-                }
-                let arg_types = self.subprogram_table.get(name).unwrap().param_types.clone();
-                for (i, arg) in args.iter().enumerate() {
-                    let _ = self.analyze_expr(arg, arg_types[i]);
-                }
-                if self.subprogram_table.get(name).unwrap().return_type != Type::Void {
-                    self.errors.push(SemError {
-                        msg: format!("subprogram {name} returns a value which is not used"),
-                        position: node.position.clone(),
-                    });
+                };
+                //A noreturn call never hands back its value so there is nothing to use
+                if return_type != Type::Void && !self.is_noreturn_call(name) {
+                    self.errors.push(Diagnostic::error(
+                        format!("subprogram {name} returns a value which is not used"),
+                        node.position.clone(),
+                    ));
                 }
             }
-            Stmts::If { expr, stmts } => {
+            Stmts::If {
+                expr,
+                stmts,
+                body_position,
+            } => {
                 //TODO: check if this is type bool
+                self.warn_empty_body("if", stmts, body_position);
                 self.begin_block();
                 let _gotten_type = self.analyze_expr(expr, Type::Unknown);
                 for stmt in stmts.iter_mut() {
@@ -396,26 +1157,122 @@ impl SemanticAnalyzer {
                 }
                 self.end_block();
             }
-            Stmts::While { expr, stmts } => {
+            Stmts::While {
+                expr,
+                stmts,
+                body_position,
+                else_stmts,
+                label,
+            } => {
                 //TODO: check if this is type bool
+                self.warn_empty_body("while", stmts, body_position);
                 self.begin_block();
                 let _gotten_type = self.analyze_expr(expr, Type::Unknown);
-                for stmt in stmts.iter_mut() {
-                    self.analyze_stmt(stmt)
-                }
+                self.analyze_loop_body(label, stmts, &node.position);
                 self.end_block();
+                //The else runs after the loop so a break in it leaves the enclosing loop
+                if let Some(else_stmts) = else_stmts {
+                    self.begin_block();
+                    for stmt in else_stmts.iter_mut() {
+                        self.analyze_stmt(stmt)
+                    }
+                    self.end_block();
+                }
             }
-            Stmts::Until { expr, stmts } => {
+            Stmts::Until { expr, stmts, label } => {
                 //TODO: check if this is type bool
                 self.begin_block();
                 let _gotten_type = self.analyze_expr(expr, Type::Unknown);
-                for stmt in stmts.iter_mut() {
-                    self.analyze_stmt(stmt)
+                self.analyze_loop_body(label, stmts, &node.position);
+                self.end_block();
+            }
+            Stmts::DoWhile { stmts, expr, label } => {
+                self.begin_block();
+                self.analyze_loop_body(label, stmts, &node.position);
+                self.end_block();
+                //Checked after the body is gone, C does not see its variables in the condition
+                let gotten_type = self.analyze_expr(expr, Type::Unknown);
+                if !matches!(gotten_type, Type::Bool | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("do while condition must be of type Bool, found {gotten_type:?}"),
+                        expr.position.clone(),
+                    ));
+                }
+            }
+            Stmts::Break(label) => self.check_loop_jump("break", label, &node.position),
+            Stmts::Continue(label) => self.check_loop_jump("continue", label, &node.position),
+            Stmts::Repeat {
+                count,
+                stmts,
+                label,
+            } => {
+                let gotten_type = self.analyze_expr(count, Type::Nat);
+                if !matches!(gotten_type, Type::Nat | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("repeat count must be of type Nat, found {gotten_type:?}"),
+                        count.position.clone(),
+                    ));
                 }
+                self.begin_block();
+                self.analyze_loop_body(label, stmts, &node.position);
+                self.end_block();
+            }
+            Stmts::For {
+                var,
+                var_type,
+                start,
+                end,
+                stmts,
+                label,
+            } => {
+                //An untyped literal bound takes its type from the other one like in a comparison
+                let (start_type, end_type) = if matches!(start.value, Expr::Number(_, None, _)) {
+                    let end_type = self.analyze_expr(end, Type::Unknown);
+                    (self.analyze_expr(start, end_type), end_type)
+                } else {
+                    let start_type = self.analyze_expr(start, Type::Unknown);
+                    (start_type, self.analyze_expr(end, start_type))
+                };
+                if !matches!(start_type, Type::Int | Type::Nat | Type::Unknown) {
+                    self.errors.push(Diagnostic::error(
+                        format!("for loop bounds must be of type Int or Nat, found {start_type:?}"),
+                        start.position.clone(),
+                    ));
+                } else if start_type != end_type && end_type != Type::Unknown {
+                    self.errors.push(Diagnostic::error(
+                        format!(
+                            "for loop bounds must have the same type, found {start_type:?} and {end_type:?}"
+                        ),
+                        end.position.clone(),
+                    ));
+                }
+                if let (Expr::Number(first, ..), Expr::Number(last, ..)) =
+                    (&start.value, &end.value)
+                    && first > last
+                {
+                    self.warn(
+                        "empty-range",
+                        format!("loop range {first} to {last} is empty"),
+                        node.position.clone(),
+                    );
+                }
+                *var_type = start_type;
+                self.begin_block();
+                self.check_new_variable(var, &node.position);
+                self.local_var_table.insert(
+                    var.clone(),
+                    VarCtx {
+                        var_type: start_type,
+                        mutable: false,
+                        decl_level: self.decl_level,
+                    },
+                );
+                self.analyze_loop_body(label, stmts, &node.position);
                 self.end_block();
             }
             //TODO: ensure it is within an if
             Stmts::Else(stmts) => {
+                self.warn_empty_body("else", stmts, &node.position);
                 self.begin_block();
                 for stmt in stmts.iter_mut() {
                     self.analyze_stmt(stmt)
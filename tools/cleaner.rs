@@ -11,7 +11,7 @@ fn main() -> io::Result<()> {
         let extension = file_path.extension();
         match extension {
             Some(ext) => {
-                if ext.to_str().unwrap() == "c" {
+                if ext.to_str().unwrap() == "c" || ext.to_str().unwrap() == "ir" {
                     println!("Removing file: {}", file_path.to_str().unwrap());
                     fs::remove_file(file_path.to_str().unwrap())?
                 }
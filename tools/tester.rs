@@ -1,7 +1,12 @@
+use pseudo::config::Config;
+use pseudo::ir::{Cir, CirGenerator};
+use pseudo::lexer::Lexer;
+use pseudo::semantic::SemanticAnalyzer;
+use std::ffi::c_int;
+use std::io::Write;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
 use std::{env, fs, io};
-use std::ffi::c_int;
 
 const HIDE_CURSOR: &str = "\x1b[?25l";
 unsafe extern "C" {
@@ -11,12 +16,12 @@ unsafe extern "C" {
 //This function unhides the cursor when we exit
 extern "C" fn clean_up() {
     unsafe {
-        printf(b"\x1b[?25h\0".as_ptr());
+        printf(c"\x1b[?25h".as_ptr().cast());
     }
 }
 
 #[used]
-#[unsafe(link_section=".fini_array")]
+#[unsafe(link_section = ".fini_array")]
 static _DESTRUCTOR: [extern "C" fn(); 1] = [clean_up];
 
 enum LogLevel {
@@ -34,20 +39,527 @@ fn pretty_print(msg: &str, level: LogLevel) {
     }
 }
 
+fn test_failed(file_path: &str, reason: &str) -> ! {
+    pretty_print(
+        &format!("Example {} failed test because of : ", file_path),
+        LogLevel::Error,
+    );
+    pretty_print(reason, LogLevel::Error);
+    std::process::exit(1);
+}
+
+fn strip_colors(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c == 'm' {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
 fn get_output_path(input_file_path: &str) -> String {
-    let mut acc = Vec::new();
-    input_file_path.split("/").for_each(|s| acc.push(s));
-    let last = acc.pop().unwrap().split(".").next().unwrap();
-    acc.push(last);
-    return acc.join("/");
+    pseudo::output_path(input_file_path).expect("Examples should have the .pseudo extension")
+}
+
+fn check_output_path() {
+    let output_path = get_output_path("a/b/my.test.pseudo");
+    if output_path != "a/b/my.test" {
+        test_failed(
+            "a/b/my.test.pseudo",
+            &format!("expected the output path a/b/my.test but got {output_path}"),
+        );
+    }
+}
+
+//--checked only changes the generated C, so it has to make it from the Config to codegen
+fn check_config() {
+    const NAME: &str = "config_check.pseudo";
+    const CHECKED_ADD: &str = "pseudo_checked_add_i32(a, 1";
+    let source = "func main(): int\nstart\n    set a := 1;\n    return a + 1;\nstop\n";
+    let compile = |config: &Config| {
+        pseudo::compile(source, NAME, config)
+            .unwrap_or_else(|_| test_failed(NAME, "the config check program should compile"))
+    };
+    if compile(&Config::default()).contains(CHECKED_ADD) {
+        test_failed(NAME, "the default config generated checked arithmetic");
+    }
+    let config = Config {
+        checked: true,
+        ..Config::default()
+    };
+    if !compile(&config).contains(CHECKED_ADD) {
+        test_failed(NAME, "a checked config did not generate checked arithmetic");
+    }
+    let config = Config {
+        prelude: false,
+        ..Config::default()
+    };
+    if compile(&config).contains("#include") {
+        test_failed(NAME, "a config without the prelude generated an #include");
+    }
+}
+
+//-o has to create the directories leading up to the executable
+fn check_nested_output(dir_path: &str) {
+    let file_path = format!("{dir_path}/hello.pseudo");
+    let out_dir = env::temp_dir().join("pseudo_tester_nested");
+    let _ = fs::remove_dir_all(&out_dir);
+    let executable_path = out_dir.join("a/b/hello");
+    let output = Command::new("cargo")
+        .args(["pseudo", &file_path, "-o"])
+        .arg(&executable_path)
+        .output()
+        .expect("Failed to run cargo pseudo command");
+    if !output.status.success() || !executable_path.exists() {
+        test_failed(
+            &file_path,
+            &format!(
+                "expected -o to create {} but got {:?}",
+                executable_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        );
+    }
+    let _ = fs::remove_dir_all(&out_dir);
+}
+
+//-I has to reach cc ahead of the libpseudo headers, so a pseudo.h in there is the one included
+fn check_include_dir(dir_path: &str) {
+    let file_path = format!("{dir_path}/hello.pseudo");
+    let include_dir = env::temp_dir().join("pseudo_tester_include");
+    fs::create_dir_all(&include_dir).expect("Failed to create the include dir");
+    fs::write(
+        include_dir.join("pseudo.h"),
+        "#error \"the include dir reached cc\"\n",
+    )
+    .expect("Failed to write the fake pseudo.h");
+    let output_path = include_dir.join("hello");
+    let output = Command::new("cargo")
+        .args(["pseudo", &file_path, "-o"])
+        .arg(&output_path)
+        .arg("--include-dir")
+        .arg(&include_dir)
+        .output()
+        .expect("Failed to run cargo pseudo command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() || !stderr.contains("the include dir reached cc") {
+        test_failed(
+            &file_path,
+            &format!("expected cc to include the pseudo.h from --include-dir but got {stderr:?}"),
+        );
+    }
+    let _ = fs::remove_dir_all(&include_dir);
+}
+
+//The fake pseudo.h only stops cc when it builds as C11, so its error shows that -std=c11
+//was passed
+fn check_c_std(dir_path: &str) {
+    let file_path = format!("{dir_path}/hello.pseudo");
+    let include_dir = env::temp_dir().join("pseudo_tester_c_std");
+    fs::create_dir_all(&include_dir).expect("Failed to create the include dir");
+    fs::write(
+        include_dir.join("pseudo.h"),
+        "#if __STDC_VERSION__ == 201112L\n#error \"c11 reached cc\"\n#endif\n",
+    )
+    .expect("Failed to write the fake pseudo.h");
+    let output_path = include_dir.join("hello");
+    let output = Command::new("cargo")
+        .args(["pseudo", &file_path, "-o"])
+        .arg(&output_path)
+        .arg("--include-dir")
+        .arg(&include_dir)
+        .args(["--c-std", "c11"])
+        .output()
+        .expect("Failed to run cargo pseudo command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() || !stderr.contains("c11 reached cc") {
+        test_failed(
+            &file_path,
+            &format!("expected cc to build with -std=c11 but got {stderr:?}"),
+        );
+    }
+    let _ = fs::remove_dir_all(&include_dir);
+}
+
+//The .error file of many_errors only shows that the right errors are there, this checks that
+//nothing past the cap is printed
+fn check_max_errors(dir_path: &str) {
+    let file_path = format!("{dir_path}/many_errors.pseudo");
+    let output = Command::new("cargo")
+        .args(["pseudo", &file_path, "--max-errors", "2"])
+        .output()
+        .expect("Failed to run cargo pseudo command");
+    let stderr = strip_colors(&String::from_utf8_lossy(&output.stderr));
+    let reported = stderr
+        .lines()
+        .filter(|line| line.starts_with(&format!("{file_path}:")))
+        .count();
+    if reported != 2 || stderr.lines().last() != Some("... and 3 more errors") {
+        test_failed(
+            &file_path,
+            &format!("expected two errors and a summary with --max-errors 2 but got {stderr:?}"),
+        );
+    }
+}
+
+//Piped stderr is not a terminal, so only --color=always should write escapes
+fn check_color(dir_path: &str) {
+    let file_path = format!("{dir_path}/many_errors.pseudo");
+    for (color, wants_escapes) in [("never", false), ("auto", false), ("always", true)] {
+        let output = Command::new("cargo")
+            .args(["pseudo", &file_path, &format!("--color={color}")])
+            .output()
+            .expect("Failed to run cargo pseudo command");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("\x1b[") != wants_escapes {
+            test_failed(
+                &file_path,
+                &format!("unexpected color escapes with --color={color} in {stderr:?}"),
+            );
+        }
+    }
+}
+
+//pseudo::compile has to agree with the binary on which examples are rejected and why,
+//and must never take the tester down with it
+fn check_library(file_path: &str) {
+    let source = fs::read_to_string(file_path).expect("Failed to read the example");
+    let expected_errors = fs::read_to_string(Path::new(file_path).with_extension("error")).ok();
+    match (
+        pseudo::compile(&source, file_path, &Config::default()),
+        expected_errors,
+    ) {
+        (Ok(_), Some(_)) => test_failed(file_path, "pseudo::compile accepted an invalid program"),
+        (Ok(code), None) => check_format(file_path, &source, &code),
+        (Err(diagnostics), expected_errors) => {
+            let reported = diagnostics
+                .iter()
+                .map(|diagnostic| strip_colors(&diagnostic.to_string()))
+                .collect::<Vec<String>>()
+                .join("\n");
+            let Some(expected_errors) = expected_errors else {
+                test_failed(
+                    file_path,
+                    &format!("pseudo::compile rejected a valid program with {reported:?}"),
+                );
+            };
+            for expected in expected_errors.lines() {
+                if !reported.contains(expected) {
+                    test_failed(
+                        file_path,
+                        &format!("pseudo::compile expected {expected:?} but got {reported:?}"),
+                    );
+                }
+            }
+        }
+    }
+}
+
+//Formatting a valid program has to give back the same program, and formatting that again
+//must not change anything
+fn check_format(file_path: &str, source: &str, code: &str) {
+    let config = Config::default();
+    let formatted = pseudo::format_source(source, file_path, &config).unwrap_or_else(|_| {
+        test_failed(file_path, "pseudo::format_source rejected a valid program")
+    });
+    //Statements move to other lines so the #line directives are left out
+    let without_lines = |code: &str| {
+        code.lines()
+            .filter(|line| !line.starts_with("#line"))
+            .collect::<Vec<&str>>()
+            .join("\n")
+    };
+    match pseudo::compile(&formatted, file_path, &config) {
+        Ok(formatted_code) if without_lines(&formatted_code) == without_lines(code) => {}
+        _ => test_failed(
+            file_path,
+            &format!("the formatted program compiles differently {formatted:?}"),
+        ),
+    }
+    let reformatted = pseudo::format_source(&formatted, file_path, &config)
+        .unwrap_or_else(|_| test_failed(file_path, "the formatted program does not parse"));
+    if reformatted != formatted {
+        test_failed(
+            file_path,
+            &format!("formatting is not stable, {formatted:?} became {reformatted:?}"),
+        );
+    }
+}
+
+//The analyzer hands its errors back instead of exiting, so they can be inspected directly
+fn check_analyzer() {
+    const NAME: &str = "analyzer_check.pseudo";
+    let source = "func main(): int\nstart\n    set x: int = true;\n    return 0;\nstop\n";
+    let mut ast = pseudo::parse(source, NAME, &Config::default())
+        .unwrap_or_else(|_| test_failed(NAME, "the analyzer check program should parse"));
+    let errors = match SemanticAnalyzer::new(&Config::default()).analyze_ast(&mut ast) {
+        Ok(()) => test_failed(NAME, "the analyzer accepted a program with a type error"),
+        Err(errors) => errors,
+    };
+    let [error] = errors.as_slice() else {
+        test_failed(
+            NAME,
+            &format!("expected exactly one error but got {errors:?}"),
+        );
+    };
+    let position = (error.position.row, error.position.column);
+    if error.msg != "Expected type Int, found boolean" || position != (3, 18) {
+        test_failed(NAME, &format!("unexpected analyzer error {error:?}"));
+    }
+}
+
+//Errors from the parser and from the analyzer are the same Diagnostic and print the same way
+fn check_diagnostic_format() {
+    const NAME: &str = "diagnostic_check.pseudo";
+    let parse_error = match pseudo::parse(
+        "func main(): int\nstart\n    set = 1;\nstop\n",
+        NAME,
+        &Config::default(),
+    ) {
+        Ok(_) => test_failed(NAME, "the parser accepted a set without a name"),
+        Err(mut errors) => errors.remove(0),
+    };
+    let mut ast = pseudo::parse(
+        "func main(): int\nstart\n    set x: int = true;\n    return 0;\nstop\n",
+        NAME,
+        &Config::default(),
+    )
+    .unwrap_or_else(|_| test_failed(NAME, "the diagnostic check program should parse"));
+    let semantic_error = match pseudo::analyze(&mut ast, &Config::default()) {
+        Ok(_) => test_failed(NAME, "the analyzer accepted a program with a type error"),
+        Err(mut errors) => errors.remove(0),
+    };
+    let rendered = [
+        strip_colors(&parse_error.to_string()),
+        strip_colors(&semantic_error.to_string()),
+    ];
+    let expected = [
+        format!("{NAME}:3:9: error: expected identifier but found ="),
+        format!("{NAME}:3:18: error: Expected type Int, found boolean"),
+    ];
+    if rendered != expected {
+        test_failed(
+            NAME,
+            &format!("expected the diagnostics {expected:?} but got {rendered:?}"),
+        );
+    }
+}
+
+//The unused warning is only found after every body was walked, it still has to come first
+fn check_warning_order() {
+    const NAME: &str = "warning_order_check.pseudo";
+    let source = "proc unused()\nstart\nstop\n\nfunc main(): int\nstart\n    if true then\n    end\n    return 0;\nstop\n";
+    let mut ast = pseudo::parse(source, NAME, &Config::default())
+        .unwrap_or_else(|_| test_failed(NAME, "the warning order program should parse"));
+    let warnings = pseudo::analyze(&mut ast, &Config::default())
+        .unwrap_or_else(|_| test_failed(NAME, "the warning order program should be accepted"));
+    let positions = warnings
+        .iter()
+        .map(|warning| (warning.position.row, warning.position.column))
+        .collect::<Vec<(usize, usize)>>();
+    let codes = warnings
+        .iter()
+        .map(|warning| warning.code)
+        .collect::<Vec<Option<&str>>>();
+    if codes != [Some("unused-subprogram"), Some("empty-body")] {
+        test_failed(
+            NAME,
+            &format!("expected the unused-subprogram and empty-body codes but got {codes:?}"),
+        );
+    }
+    if positions.len() != 2 || !positions.is_sorted() {
+        test_failed(
+            NAME,
+            &format!("expected two warnings in source order but got {warnings:?}"),
+        );
+    }
+}
+
+//Statements after a return in the same block never run and should not reach the IR
+fn check_unreachable_dropped() {
+    const NAME: &str = "unreachable_check.pseudo";
+    let source = "func main(): int\nstart\n    if true then\n        return 1;\n        write(2);\n    end\n    return 0;\n    write(3);\nstop\n";
+    let mut ast = pseudo::parse(source, NAME, &Config::default())
+        .unwrap_or_else(|_| test_failed(NAME, "the unreachable check program should parse"));
+    if pseudo::analyze(&mut ast, &Config::default()).is_err() {
+        test_failed(NAME, "the unreachable check program should be accepted");
+    }
+    let ir = CirGenerator::new(&Config::default()).generate_cir(ast);
+    let [main] = ir.as_slice() else {
+        test_failed(
+            NAME,
+            &format!("expected only main in the ir but got {ir:?}"),
+        );
+    };
+    let Cir::SubProgDef { stmts_cir, .. } = &main.value else {
+        test_failed(
+            NAME,
+            &format!("expected main to be a subprogram but got {main:?}"),
+        );
+    };
+    let dropped = match stmts_cir.as_slice() {
+        [if_stmt, return_stmt] => match (&if_stmt.value, &return_stmt.value) {
+            (Cir::If(_, if_stmts), Cir::Return(_)) => {
+                matches!(if_stmts.as_slice(), [stmt] if matches!(stmt.value, Cir::Return(_)))
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    if !dropped {
+        test_failed(
+            NAME,
+            &format!("expected the writes after each return to be dropped but got {stmts_cir:?}"),
+        );
+    }
+}
+
+//tokenize keeps going to the end and stops right after the one Eof
+fn check_tokenize() {
+    const NAME: &str = "tokenize_check.pseudo";
+    let source = "set x := 1;\n// a comment at the end\n\n";
+    let tokens = Lexer::new(NAME.to_string(), source.to_string(), &Config::default()).tokenize();
+    let kinds = tokens
+        .iter()
+        .map(|token| token.kind.to_string())
+        .collect::<Vec<String>>();
+    let expected = ["set", "identifier \"x\"", ":=", "number \"1\"", ";", "eof"];
+    if kinds != expected {
+        test_failed(
+            NAME,
+            &format!("expected the tokens {expected:?} but got {kinds:?}"),
+        );
+    }
+}
+
+//Tokens keep the column counted in characters next to the one tabs are expanded in
+fn check_tab_columns() {
+    const NAME: &str = "tab_columns_check.pseudo";
+    for (tab_width, expected) in [(4, [(2, 5), (6, 9)]), (8, [(2, 9), (6, 17)])] {
+        let config = Config {
+            tab_width,
+            ..Config::default()
+        };
+        let tokens = Lexer::new(NAME.to_string(), "\tset\tx".to_string(), &config).tokenize();
+        let columns = tokens
+            .iter()
+            .take(2)
+            .map(|token| (token.raw_column, token.column))
+            .collect::<Vec<(usize, usize)>>();
+        if columns != expected {
+            test_failed(
+                NAME,
+                &format!(
+                    "expected the raw and visual columns {expected:?} with a tab width of {tab_width} but got {columns:?}"
+                ),
+            );
+        }
+    }
+}
+
+//Lowering keeps the position of the write so the #line before it points at its source row
+fn check_write_position() {
+    const NAME: &str = "write_position_check.pseudo";
+    let source =
+        "func main(): int\nstart\n    set x: int = 1;\n\n      writeln(x);\n    return 0;\nstop\n";
+    let mut ast = pseudo::parse(source, NAME, &Config::default())
+        .unwrap_or_else(|_| test_failed(NAME, "the write position check program should parse"));
+    if pseudo::analyze(&mut ast, &Config::default()).is_err() {
+        test_failed(NAME, "the write position check program should be accepted");
+    }
+    let ir = CirGenerator::new(&Config::default()).generate_cir(ast);
+    let Some(Cir::SubProgDef { stmts_cir, .. }) = ir.first().map(|main| &main.value) else {
+        test_failed(
+            NAME,
+            &format!("expected main to be a subprogram but got {ir:?}"),
+        );
+    };
+    let Some(write) = stmts_cir
+        .iter()
+        .find(|stmt| matches!(stmt.value, Cir::Write(..)))
+    else {
+        test_failed(
+            NAME,
+            &format!("expected a write in main but got {stmts_cir:?}"),
+        );
+    };
+    if (write.position.row, write.position.column) != (5, 7) {
+        test_failed(
+            NAME,
+            &format!(
+                "expected the lowered write at 5:7 but got {}:{}",
+                write.position.row, write.position.column
+            ),
+        );
+    }
+}
+
+fn check_dump(file_path: &str, extension: &str, flag: &str) {
+    let dump_path = Path::new(file_path).with_extension(extension);
+    if let Ok(expected_dump) = fs::read_to_string(&dump_path) {
+        let output = Command::new("cargo")
+            .args(["pseudo", file_path, flag])
+            .output()
+            .expect("Failed to run cargo pseudo command");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() || stdout != expected_dump {
+            test_failed(
+                file_path,
+                &format!("expected {extension} {expected_dump:?} but got {stdout:?}"),
+            );
+        }
+    }
 }
 
 fn run_test(file_path: &str) {
     eprint!("\rCompiling file {file_path}                              ");
+    //If there is an .ast, .tokens, .fmt, .types or .cir file next to the example, --dump-ast,
+    //--dump-tokens, --fmt, --explain-types or --emit-ir=pretty must print exactly that
+    check_dump(file_path, "ast", "--dump-ast");
+    check_dump(file_path, "tokens", "--dump-tokens");
+    check_dump(file_path, "fmt", "--fmt");
+    check_dump(file_path, "types", "--explain-types");
+    check_dump(file_path, "cir", "--emit-ir=pretty");
+
+    //If there is an .args file next to the example, its flags are passed to the compiler.
+    //Those change what gets compiled so only plain examples go through the library too
+    let extra_args = fs::read_to_string(Path::new(file_path).with_extension("args"));
+    if extra_args.is_err() {
+        check_library(file_path);
+    }
+    let extra_args = extra_args.unwrap_or_default();
     let output = Command::new("cargo")
-        .args(["pseudo", file_path, "--keep"])
+        .args(["pseudo", file_path, "--keep-ir"])
+        .args(extra_args.split_whitespace())
         .output()
         .expect("Failed to run cargo pseudo command");
+
+    //If there is an .error file next to the example, compilation must fail
+    //and every line in it must show up in the diagnostics
+    let error_path = Path::new(file_path).with_extension("error");
+    if let Ok(expected_errors) = fs::read_to_string(&error_path) {
+        if output.status.success() {
+            test_failed(file_path, "expected compilation to fail but it succeeded");
+        }
+        let stderr = strip_colors(&String::from_utf8_lossy(&output.stderr));
+        for expected in expected_errors.lines() {
+            if !stderr.contains(expected) {
+                test_failed(
+                    file_path,
+                    &format!("expected diagnostic {expected:?} but got {stderr:?}"),
+                );
+            }
+        }
+        return;
+    }
     if !output.status.success() {
         pretty_print(
             &format!("Example {} failed test because of : ", file_path),
@@ -59,13 +571,69 @@ fn run_test(file_path: &str) {
         );
         std::process::exit(1);
     }
+    //If there is a .warning file next to the example, every line in it must show up in the
+    //diagnostics, otherwise the example has to compile without warnings
+    let stderr = strip_colors(&String::from_utf8_lossy(&output.stderr));
+    let warning_path = Path::new(file_path).with_extension("warning");
+    match fs::read_to_string(&warning_path) {
+        Ok(expected_warnings) => {
+            for expected in expected_warnings.lines() {
+                if !stderr.contains(expected) {
+                    test_failed(
+                        file_path,
+                        &format!("expected warning {expected:?} but got {stderr:?}"),
+                    );
+                }
+            }
+        }
+        Err(_) => {
+            let prefix = format!("{file_path}:");
+            if let Some(warning) = stderr.lines().find(|line| line.starts_with(&prefix)) {
+                test_failed(file_path, &format!("unexpected warning {warning:?}"));
+            }
+        }
+    }
+
     let executable_path = get_output_path(file_path);
+    if !Path::new(&format!("{executable_path}.ir")).exists() {
+        test_failed(file_path, "--keep-ir did not write the ir file");
+    }
 
     eprint!("\rRunning file {executable_path}                              ");
-    let output = Command::new(executable_path)
-        .output()
-        .expect(&format!("Failed to execute {file_path} command"));
-    if !output.status.success() {
+    //If there is a .stdin file next to the example, it is fed to the program
+    let stdin = fs::read(Path::new(file_path).with_extension("stdin")).unwrap_or_default();
+    let mut child = Command::new(&executable_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|_| panic!("Failed to execute {file_path} command"));
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&stdin)
+        .expect("Failed to write to the stdin of the example");
+    let output = child
+        .wait_with_output()
+        .unwrap_or_else(|_| panic!("Failed to execute {file_path} command"));
+    //If there is a .status file next to the example, the program must exit with that code
+    let status_path = Path::new(file_path).with_extension("status");
+    if let Ok(expected_status) = fs::read_to_string(&status_path) {
+        let expected_status = expected_status
+            .trim()
+            .parse::<i32>()
+            .expect("The .status file should contain an exit code");
+        if output.status.code() != Some(expected_status) {
+            test_failed(
+                file_path,
+                &format!(
+                    "expected exit status {expected_status} but got {:?}",
+                    output.status.code()
+                ),
+            );
+        }
+    } else if !output.status.success() {
         pretty_print(
             &format!("Example {} failed test because of : ", file_path),
             LogLevel::Error,
@@ -76,6 +644,53 @@ fn run_test(file_path: &str) {
         );
         std::process::exit(1);
     }
+
+    //If there is a .stderr file next to the example, every line in it must show up in what
+    //the program printed to stderr
+    let stderr_path = Path::new(file_path).with_extension("stderr");
+    if let Ok(expected_stderr) = fs::read_to_string(&stderr_path) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        for expected in expected_stderr.lines() {
+            if !stderr.contains(expected) {
+                test_failed(
+                    file_path,
+                    &format!("expected {expected:?} on stderr but got {stderr:?}"),
+                );
+            }
+        }
+    }
+
+    //If there is a .gen file next to the example, its lines must be in the generated C in the
+    //same order. {file} stands for the path of the example, as #line directives spell it
+    let gen_path = Path::new(file_path).with_extension("gen");
+    if let Ok(expected_lines) = fs::read_to_string(&gen_path) {
+        let c_code = fs::read_to_string(format!("{executable_path}.c"))
+            .expect("--keep-ir should have kept the generated C");
+        let mut c_lines = c_code.lines();
+        for expected in expected_lines.lines() {
+            let expected = expected.replace("{file}", file_path);
+            if !c_lines.any(|line| line == expected) {
+                test_failed(
+                    file_path,
+                    &format!(
+                        "expected the generated C to contain the line {expected:?} after the previous ones"
+                    ),
+                );
+            }
+        }
+    }
+
+    //If there is an .expected file next to the example, its contents must match stdout
+    let expected_path = Path::new(file_path).with_extension("expected");
+    if let Ok(expected) = fs::read_to_string(&expected_path) {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if stdout != expected {
+            test_failed(
+                file_path,
+                &format!("expected output {expected:?} but got {stdout:?}"),
+            );
+        }
+    }
 }
 
 fn main() -> io::Result<()> {
@@ -86,26 +701,35 @@ fn main() -> io::Result<()> {
     }
 
     eprint!("{HIDE_CURSOR}");
+    check_analyzer();
+    check_config();
+    check_tokenize();
+    check_tab_columns();
+    check_unreachable_dropped();
+    check_write_position();
+    check_warning_order();
+    check_diagnostic_format();
+    check_output_path();
     let mut i = 1;
     let dir_path = &args[1];
+    check_nested_output(dir_path);
+    check_include_dir(dir_path);
+    check_c_std(dir_path);
+    check_max_errors(dir_path);
+    check_color(dir_path);
     for entry in fs::read_dir(dir_path)? {
         let entry = entry?;
         let file_path = &entry.path();
         let file_path = Path::new(file_path);
         let extension = file_path.extension();
-        match extension {
-            Some(ext) => {
-                let ext = ext.to_str().unwrap();
-                if ext == "pseudo" {
-                    let file_path = file_path.to_str().unwrap();
-                    eprint!(
-                        "\rRunning test: {i} on file: {file_path}                           \n"
-                    );
-                    i += 1;
-                    run_test(file_path);
-                }
+        if let Some(ext) = extension {
+            let ext = ext.to_str().unwrap();
+            if ext == "pseudo" {
+                let file_path = file_path.to_str().unwrap();
+                eprint!("\rRunning test: {i} on file: {file_path}                           \n");
+                i += 1;
+                run_test(file_path);
             }
-            None => {}
         }
     }
     pretty_print("\rDone! All tests passed succesfully!", LogLevel::Success);